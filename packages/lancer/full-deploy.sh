@@ -64,9 +64,18 @@ echo "=========================================="
 
 cat > src/lib.rs << 'EOF'
 #![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
 pub mod protocol;
 // pub mod marketplace;
 // pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
 EOF
 
 cat > src/main.rs << 'EOF'
@@ -79,8 +88,8 @@ fn main() {
 EOF
 
 cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
-PROTOCOL_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
-echo "Protocol deployed at: $PROTOCOL_ADDR"
+PROTOCOL_IMPL=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Protocol implementation deployed at: $PROTOCOL_IMPL"
 echo ""
 
 # Deploy Marketplace
@@ -90,9 +99,18 @@ echo "=========================================="
 
 cat > src/lib.rs << 'EOF'
 #![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
 // pub mod protocol;
 pub mod marketplace;
 // pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
 EOF
 
 cat > src/main.rs << 'EOF'
@@ -105,8 +123,39 @@ fn main() {
 EOF
 
 cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+MARKETPLACE_IMPL=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Marketplace implementation deployed at: $MARKETPLACE_IMPL"
+echo ""
+
+# Deploy Proxies
+echo "=========================================="
+echo "📦 Deploying Proxies"
+echo "=========================================="
+
+cat > src/lib.rs << 'EOF'
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+// pub mod protocol;
+// pub mod marketplace;
+// pub mod mocks;
+pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
+EOF
+
+# Only the deployer may initialize the fresh proxies
+export LANCER_PROXY_INITIALIZER=$DEPLOYER_ADDR
+cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+PROTOCOL_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Protocol proxy deployed at: $PROTOCOL_ADDR"
 MARKETPLACE_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
-echo "Marketplace deployed at: $MARKETPLACE_ADDR"
+echo "Marketplace proxy deployed at: $MARKETPLACE_ADDR"
+unset LANCER_PROXY_INITIALIZER
 echo ""
 
 # Deploy Mock USDC
@@ -116,9 +165,18 @@ echo "=========================================="
 
 cat > src/lib.rs << 'EOF'
 #![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
 // pub mod protocol;
 // pub mod marketplace;
 pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
 EOF
 
 cat > src/main.rs << 'EOF'
@@ -135,6 +193,84 @@ USDC_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoi
 echo "Mock USDC deployed at: $USDC_ADDR"
 echo ""
 
+# Deploy Forwarder
+echo "=========================================="
+echo "📦 Deploying Meta-Transaction Forwarder"
+echo "=========================================="
+
+cat > src/lib.rs << 'EOF'
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+// pub mod protocol;
+// pub mod marketplace;
+// pub mod mocks;
+// pub mod proxy;
+pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
+EOF
+
+cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+FORWARDER_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Forwarder deployed at: $FORWARDER_ADDR"
+echo ""
+
+# Deploy Credentials
+echo "=========================================="
+echo "📦 Deploying Soulbound Credentials"
+echo "=========================================="
+
+cat > src/lib.rs << 'EOF'
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+// pub mod protocol;
+// pub mod marketplace;
+// pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+pub mod credentials;
+// pub mod positions;
+EOF
+
+cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+CREDENTIALS_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Credentials deployed at: $CREDENTIALS_ADDR"
+echo ""
+
+# Deploy Deal Positions
+echo "=========================================="
+echo "📦 Deploying Deal Positions"
+echo "=========================================="
+
+cat > src/lib.rs << 'EOF'
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+// pub mod protocol;
+// pub mod marketplace;
+// pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+pub mod positions;
+EOF
+
+cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+POSITIONS_ADDR=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "Positions deployed at: $POSITIONS_ADDR"
+echo ""
+
 # Initialize contracts
 echo "=========================================="
 echo "⚙️  Initializing Contracts"
@@ -144,14 +280,39 @@ echo "Initializing Mock USDC..."
 cast send $USDC_ADDR "init(address)" $DEPLOYER_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
 echo "✅ Mock USDC initialized"
 
-echo "Initializing Protocol..."
-cast send $PROTOCOL_ADDR "init(address,address)" $DEPLOYER_ADDR $USDC_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+echo "Initializing Protocol (through proxy)..."
+PROTOCOL_INIT=$(cast calldata "init(address,address)" $DEPLOYER_ADDR $USDC_ADDR)
+cast send $PROTOCOL_ADDR "initProxy(address,bytes)" $PROTOCOL_IMPL $PROTOCOL_INIT --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
 cast send $PROTOCOL_ADDR "updateNumberOfVotes(uint8)" 3 --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
 echo "✅ Protocol initialized (3 votes required)"
 
-echo "Initializing Marketplace..."
-cast send $MARKETPLACE_ADDR "init(address,uint8,address,address)" $DEPLOYER_ADDR 5 $USDC_ADDR $PROTOCOL_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+echo "Initializing Marketplace (through proxy)..."
+MARKETPLACE_INIT=$(cast calldata "init(address,uint8,address,address)" $DEPLOYER_ADDR 5 $USDC_ADDR $PROTOCOL_ADDR)
+cast send $MARKETPLACE_ADDR "initProxy(address,bytes)" $MARKETPLACE_IMPL $MARKETPLACE_INIT --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
 echo "✅ Marketplace initialized"
+
+echo "Granting ARBITRABLE_ROLE to Marketplace..."
+cast send $PROTOCOL_ADDR "grantRole(bytes32,address)" $(cast keccak "ARBITRABLE_ROLE") $MARKETPLACE_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+echo "✅ Marketplace can open disputes in Protocol"
+
+echo "Trusting the forwarder for meta-transactions..."
+cast send $PROTOCOL_ADDR "setTrustedForwarder(address)" $FORWARDER_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+cast send $MARKETPLACE_ADDR "setTrustedForwarder(address)" $FORWARDER_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+echo "✅ Protocol and Marketplace accept forwarded calls"
+
+echo "Initializing Credentials..."
+cast send $CREDENTIALS_ADDR "init(address)" $DEPLOYER_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+for minter in $PROTOCOL_ADDR $MARKETPLACE_ADDR; do
+    cast send $CREDENTIALS_ADDR "grantRole(bytes32,address)" $(cast keccak "MINTER_ROLE") $minter --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+    cast send $minter "setCredentials(address)" $CREDENTIALS_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+done
+echo "✅ Protocol and Marketplace mint credentials"
+
+echo "Initializing Deal Positions..."
+cast send $POSITIONS_ADDR "init(address)" $DEPLOYER_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+cast send $POSITIONS_ADDR "grantRole(bytes32,address)" $(cast keccak "MINTER_ROLE") $MARKETPLACE_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+cast send $MARKETPLACE_ADDR "setPositions(address)" $POSITIONS_ADDR --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null 2>&1
+echo "✅ Marketplace mints deal positions"
 echo ""
 
 # Mint USDC to buyer
@@ -173,14 +334,25 @@ echo "=========================================="
 cat > build/stylus-deployment-info.json << EOF
 {
   "protocol_contract": {
-    "address": "$PROTOCOL_ADDR"
+    "address": "$PROTOCOL_ADDR",
+    "implementation": "$PROTOCOL_IMPL"
   },
   "marketplace_contract": {
-    "address": "$MARKETPLACE_ADDR"
+    "address": "$MARKETPLACE_ADDR",
+    "implementation": "$MARKETPLACE_IMPL"
   },
   "usdc_contract": {
     "address": "$USDC_ADDR"
   },
+  "forwarder_contract": {
+    "address": "$FORWARDER_ADDR"
+  },
+  "credentials_contract": {
+    "address": "$CREDENTIALS_ADDR"
+  },
+  "positions_contract": {
+    "address": "$POSITIONS_ADDR"
+  },
   "rpc_url": "$RPC_URL",
   "chain_id": 412346
 }
@@ -192,7 +364,10 @@ echo ""
 echo "=========================================="
 echo "✅ Deployment Complete!"
 echo "=========================================="
-echo "Protocol:    $PROTOCOL_ADDR"
-echo "Marketplace: $MARKETPLACE_ADDR"
+echo "Protocol:    $PROTOCOL_ADDR (impl $PROTOCOL_IMPL)"
+echo "Marketplace: $MARKETPLACE_ADDR (impl $MARKETPLACE_IMPL)"
 echo "Mock USDC:   $USDC_ADDR"
+echo "Forwarder:   $FORWARDER_ADDR"
+echo "Credentials: $CREDENTIALS_ADDR"
+echo "Positions:   $POSITIONS_ADDR"
 echo "=========================================="
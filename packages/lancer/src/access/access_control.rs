@@ -0,0 +1,186 @@
+//!
+//! Lancer Access Control - Arbitrum Stylus Implementation
+//!
+//! Role-based access control shared by the Marketplace, Protocol and Mock USDC
+//! contracts. Each role has an admin role whose members can grant and revoke it,
+//! and any account can renounce a role it holds.
+//!
+//! Contracts embed it with `#[borrow] AccessControl access_control;` and
+//! `#[inherit(AccessControl)]` to expose the public role management functions.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes},
+    prelude::*,
+    evm,
+};
+use crate::metatx::msg_sender;
+
+// ====================================
+//          STORAGE STRUCTS
+// ====================================
+
+sol_storage! {
+    pub struct AccessControl {
+        mapping(bytes32 => RoleData) roles;
+    }
+
+    pub struct RoleData {
+        mapping(address => bool) members;
+        bytes32 admin_role;
+    }
+}
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleAdminChanged(bytes32 indexed role, bytes32 indexed previous_admin_role, bytes32 indexed new_admin_role);
+
+    error AccessControlUnauthorizedAccount(address account, bytes32 needed_role);
+    error AccessControlBadConfirmation();
+}
+
+// ====================================
+//          ERROR TYPES
+// ====================================
+
+#[derive(SolidityError)]
+pub enum AccessControlError {
+    UnauthorizedAccount(AccessControlUnauthorizedAccount),
+    BadConfirmation(AccessControlBadConfirmation),
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// Admin of every role by default, held by the deployer
+pub const ADMIN_ROLE: FixedBytes<32> = FixedBytes::ZERO;
+/// keccak256("FEE_MANAGER_ROLE") - can update marketplace fees
+pub const FEE_MANAGER_ROLE: FixedBytes<32> =
+    fixed_bytes!("6c0757dc3e6b28b2580c03fd9e96c274acf4f99d91fbec9b418fa1d70604ff1c");
+/// keccak256("PAUSER_ROLE") - can pause and unpause a contract
+pub const PAUSER_ROLE: FixedBytes<32> =
+    fixed_bytes!("65d7a28e3265b37a6474929f336521b332c1681b933f6cb9f3376673440d862a");
+/// keccak256("JUDGE_MANAGER_ROLE") - can update the judging parameters of the protocol
+pub const JUDGE_MANAGER_ROLE: FixedBytes<32> =
+    fixed_bytes!("198ab0f8b6bdeb62db08eb618ae385e27cde8ac651e3bd8fb01a1609af77e639");
+/// keccak256("ARBITRABLE_ROLE") - contracts allowed to open and update disputes in the protocol
+pub const ARBITRABLE_ROLE: FixedBytes<32> =
+    fixed_bytes!("0894d010f349dadd00db540d4621faece0a6f2a69c97c6d8efd37f99ad1d6be9");
+/// keccak256("MINTER_ROLE") - contracts allowed to mint Lancer credentials and deal positions
+pub const MINTER_ROLE: FixedBytes<32> =
+    fixed_bytes!("9f2df0fed2c77648de5860a4cc508cd0818c85b8b8a1ab4ceeef8d981c8956a6");
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+#[public]
+impl AccessControl {
+
+    /// Check if an account has been granted a role
+    pub fn has_role(&self, role: FixedBytes<32>, account: Address) -> bool {
+        self.roles.get(role).members.get(account)
+    }
+
+    /// Get the admin role that controls a role
+    pub fn get_role_admin(&self, role: FixedBytes<32>) -> FixedBytes<32> {
+        self.roles.get(role).admin_role.get()
+    }
+
+    /// Grant a role to an account (only members of the role's admin role)
+    pub fn grant_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<(), AccessControlError> {
+        let admin_role = self.get_role_admin(role);
+        self.only_role(admin_role, msg_sender())?;
+
+        self._grant_role(role, account);
+        Ok(())
+    }
+
+    /// Revoke a role from an account (only members of the role's admin role)
+    pub fn revoke_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<(), AccessControlError> {
+        let admin_role = self.get_role_admin(role);
+        self.only_role(admin_role, msg_sender())?;
+
+        self._revoke_role(role, account);
+        Ok(())
+    }
+
+    /// Renounce a role held by the caller
+    /// `caller_confirmation` must be the caller address to prevent accidental renounces
+    pub fn renounce_role(&mut self, role: FixedBytes<32>, caller_confirmation: Address) -> Result<(), AccessControlError> {
+        if caller_confirmation != msg_sender() {
+            return Err(AccessControlError::BadConfirmation(AccessControlBadConfirmation {}));
+        }
+
+        self._revoke_role(role, caller_confirmation);
+        Ok(())
+    }
+}
+
+impl AccessControl {
+
+    /// Revert unless `account` has been granted `role`
+    pub fn only_role(&self, role: FixedBytes<32>, account: Address) -> Result<(), AccessControlError> {
+        if !self.has_role(role, account) {
+            return Err(AccessControlError::UnauthorizedAccount(AccessControlUnauthorizedAccount {
+                account,
+                needed_role: role,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Grant a role without checking the caller, returns false if already granted
+    pub fn _grant_role(&mut self, role: FixedBytes<32>, account: Address) -> bool {
+        if self.has_role(role, account) {
+            return false;
+        }
+
+        self.roles.setter(role).members.setter(account).set(true);
+        evm::log(RoleGranted {
+            role,
+            account,
+            sender: msg_sender(),
+        });
+        true
+    }
+
+    /// Revoke a role without checking the caller, returns false if not granted
+    pub fn _revoke_role(&mut self, role: FixedBytes<32>, account: Address) -> bool {
+        if !self.has_role(role, account) {
+            return false;
+        }
+
+        self.roles.setter(role).members.setter(account).set(false);
+        evm::log(RoleRevoked {
+            role,
+            account,
+            sender: msg_sender(),
+        });
+        true
+    }
+
+    /// Change the admin role of a role
+    pub fn _set_role_admin(&mut self, role: FixedBytes<32>, admin_role: FixedBytes<32>) {
+        let previous_admin_role = self.get_role_admin(role);
+        self.roles.setter(role).admin_role.set(admin_role);
+        evm::log(RoleAdminChanged {
+            role,
+            previous_admin_role,
+            new_admin_role: admin_role,
+        });
+    }
+}
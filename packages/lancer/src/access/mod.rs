@@ -0,0 +1,2 @@
+pub mod access_control;
+pub use access_control::*;
@@ -0,0 +1,318 @@
+//!
+//! Lancer Credentials - Arbitrum Stylus Implementation
+//!
+//! Soulbound (ERC-5192) ERC-721 credentials proving a Lancer track record:
+//! the Marketplace mints one to the beneficiary of every delivered deal (completed,
+//! expired in their favor or won in a dispute), the Protocol one to every judge
+//! voting with the final ruling.
+//!
+//! Credentials are locked to their owner: transfers and approvals always revert.
+//! Their metadata (kind, deal or dispute id, amount bucket and issue date) is
+//! stored on-chain and served as a JSON data URI by `tokenURI`.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use alloy_sol_types::sol;
+use crate::access::{AccessControl, AccessControlError, ADMIN_ROLE, MINTER_ROLE};
+use crate::metatx::msg_sender;
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256, U64, U8},
+    prelude::*,
+    block,
+    evm,
+};
+
+// ====================================
+//          STORAGE STRUCTS
+// ====================================
+
+sol_storage! {
+    #[entrypoint]
+    pub struct LancerCredentials {
+        #[borrow]
+        AccessControl access_control;
+        bool initialized;
+
+        uint256 total_supply;  // Token ids start at 1
+        mapping(uint256 => address) owners;
+        mapping(address => uint256) balances;
+        mapping(uint256 => Credential) credentials;
+    }
+
+    pub struct Credential {
+        uint8 kind;  // CredentialKind
+        uint64 reference_id;  // Deal id, or dispute id for rulings
+        uint8 amount_bucket;  // Deal size tier, 0 when not applicable
+        uint64 issued_at;
+    }
+}
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
+    event Locked(uint256 token_id);
+    event CredentialIssued(uint256 indexed token_id, address indexed owner, uint8 kind, uint64 reference_id, uint8 amount_bucket);
+
+    error Unauthorized();
+    error AlreadyInitialized();
+    error InvalidAddress();
+    error InvalidInput();
+    error NonexistentToken();
+    error Soulbound();
+}
+
+// ====================================
+//          ERROR TYPES
+// ====================================
+
+#[derive(SolidityError)]
+pub enum CredentialsError {
+    Unauthorized(Unauthorized),
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidAddress(InvalidAddress),
+    InvalidInput(InvalidInput),
+    NonexistentToken(NonexistentToken),
+    Soulbound(Soulbound),
+}
+
+// Missing roles surface as Unauthorized
+impl From<AccessControlError> for CredentialsError {
+    fn from(_error: AccessControlError) -> Self {
+        CredentialsError::Unauthorized(Unauthorized {})
+    }
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+const NAME: &str = "Lancer Credentials";
+const SYMBOL: &str = "LANCER";
+
+/// Credential kinds
+pub const KIND_COMPLETED_DEAL: u8 = 0;
+pub const KIND_COHERENT_RULING: u8 = 1;
+
+const INTERFACE_ERC165: FixedBytes<4> = fixed_bytes!("01ffc9a7");
+const INTERFACE_ERC721: FixedBytes<4> = fixed_bytes!("80ac58cd");
+const INTERFACE_ERC721_METADATA: FixedBytes<4> = fixed_bytes!("5b5e139f");
+const INTERFACE_ERC5192: FixedBytes<4> = fixed_bytes!("b45a3c0e");
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+#[public]
+#[inherit(AccessControl)]
+impl LancerCredentials {
+
+    /// Initialize the collection, `owner` receives the admin role
+    /// Grant MINTER_ROLE to the Marketplace and Protocol afterwards
+    pub fn init(&mut self, owner: Address) -> Result<(), CredentialsError> {
+        if self.initialized.get() {
+            return Err(CredentialsError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+
+        self.initialized.set(true);
+        self.access_control._grant_role(ADMIN_ROLE, owner);
+
+        Ok(())
+    }
+
+    // ====================================
+    //            MINTING
+    // ====================================
+
+    /// Mint a locked credential to `to` (only minter)
+    pub fn mint(
+        &mut self,
+        to: Address,
+        kind: u8,
+        reference_id: u64,
+        amount_bucket: u8,
+    ) -> Result<U256, CredentialsError> {
+        self.access_control.only_role(MINTER_ROLE, msg_sender())?;
+
+        if to == Address::ZERO {
+            return Err(CredentialsError::InvalidAddress(InvalidAddress {}));
+        }
+        if kind > KIND_COHERENT_RULING {
+            return Err(CredentialsError::InvalidInput(InvalidInput {}));
+        }
+
+        let token_id = self.total_supply.get() + U256::from(1);
+        self.total_supply.set(token_id);
+        self.owners.setter(token_id).set(to);
+        let balance = self.balances.get(to);
+        self.balances.setter(to).set(balance + U256::from(1));
+
+        let mut credential = self.credentials.setter(token_id);
+        credential.kind.set(U8::from(kind));
+        credential.reference_id.set(U64::from(reference_id));
+        credential.amount_bucket.set(U8::from(amount_bucket));
+        credential.issued_at.set(U64::from(block::timestamp()));
+
+        evm::log(Transfer {
+            from: Address::ZERO,
+            to,
+            token_id,
+        });
+        evm::log(Locked { token_id });
+        evm::log(CredentialIssued {
+            token_id,
+            owner: to,
+            kind,
+            reference_id,
+            amount_bucket,
+        });
+
+        Ok(token_id)
+    }
+
+    // ====================================
+    //        ERC721 FUNCTIONS
+    // ====================================
+
+    /// Get collection name
+    pub fn name(&self) -> String {
+        String::from(NAME)
+    }
+
+    /// Get collection symbol
+    pub fn symbol(&self) -> String {
+        String::from(SYMBOL)
+    }
+
+    /// Get number of credentials issued
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    /// Get number of credentials held by an account
+    pub fn balance_of(&self, owner: Address) -> Result<U256, CredentialsError> {
+        if owner == Address::ZERO {
+            return Err(CredentialsError::InvalidAddress(InvalidAddress {}));
+        }
+        Ok(self.balances.get(owner))
+    }
+
+    /// Get owner of a credential
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, CredentialsError> {
+        let owner = self.owners.get(token_id);
+        if owner == Address::ZERO {
+            return Err(CredentialsError::NonexistentToken(NonexistentToken {}));
+        }
+        Ok(owner)
+    }
+
+    /// Get the on-chain JSON metadata of a credential
+    #[selector(name = "tokenURI")]
+    pub fn token_uri(&self, token_id: U256) -> Result<String, CredentialsError> {
+        self.owner_of(token_id)?;
+
+        let credential = self.credentials.get(token_id);
+        let kind = u8::from_le_bytes(credential.kind.get().to_le_bytes());
+        let reference_id = u64::from_le_bytes(credential.reference_id.get().to_le_bytes());
+        let amount_bucket = u8::from_le_bytes(credential.amount_bucket.get().to_le_bytes());
+        let issued_at = u64::from_le_bytes(credential.issued_at.get().to_le_bytes());
+
+        let (kind_name, reference_name) = if kind == KIND_COHERENT_RULING {
+            ("Coherent ruling", "Dispute")
+        } else {
+            ("Completed deal", "Deal")
+        };
+
+        Ok(format!(
+            "data:application/json;utf8,{{\"name\":\"Lancer Credential #{}\",\"description\":\"Non-transferable Lancer track record\",\"attributes\":[{{\"trait_type\":\"Kind\",\"value\":\"{}\"}},{{\"trait_type\":\"{}\",\"value\":{}}},{{\"trait_type\":\"Amount bucket\",\"value\":{}}},{{\"display_type\":\"date\",\"trait_type\":\"Issued\",\"value\":{}}}]}}",
+            token_id, kind_name, reference_name, reference_id, amount_bucket, issued_at,
+        ))
+    }
+
+    /// Credentials are never approved for transfer
+    pub fn get_approved(&self, token_id: U256) -> Result<Address, CredentialsError> {
+        self.owner_of(token_id)?;
+        Ok(Address::ZERO)
+    }
+
+    /// Credentials are never approved for transfer
+    pub fn is_approved_for_all(&self, _owner: Address, _operator: Address) -> bool {
+        false
+    }
+
+    /// Credentials are soulbound, always reverts
+    pub fn approve(&mut self, _to: Address, _token_id: U256) -> Result<(), CredentialsError> {
+        Err(CredentialsError::Soulbound(Soulbound {}))
+    }
+
+    /// Credentials are soulbound, always reverts
+    pub fn set_approval_for_all(&mut self, _operator: Address, _approved: bool) -> Result<(), CredentialsError> {
+        Err(CredentialsError::Soulbound(Soulbound {}))
+    }
+
+    /// Credentials are soulbound, always reverts
+    pub fn transfer_from(&mut self, _from: Address, _to: Address, _token_id: U256) -> Result<(), CredentialsError> {
+        Err(CredentialsError::Soulbound(Soulbound {}))
+    }
+
+    /// Credentials are soulbound, always reverts
+    pub fn safe_transfer_from(&mut self, _from: Address, _to: Address, _token_id: U256) -> Result<(), CredentialsError> {
+        Err(CredentialsError::Soulbound(Soulbound {}))
+    }
+
+    /// Credentials are soulbound, always reverts
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data(
+        &mut self,
+        _from: Address,
+        _to: Address,
+        _token_id: U256,
+        _data: Vec<u8>,
+    ) -> Result<(), CredentialsError> {
+        Err(CredentialsError::Soulbound(Soulbound {}))
+    }
+
+    // ====================================
+    //        ERC5192 / ERC165
+    // ====================================
+
+    /// Every credential is locked to its owner (ERC-5192)
+    pub fn locked(&self, token_id: U256) -> Result<bool, CredentialsError> {
+        self.owner_of(token_id)?;
+        Ok(true)
+    }
+
+    /// ERC-165 interface detection
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        interface_id == INTERFACE_ERC165
+            || interface_id == INTERFACE_ERC721
+            || interface_id == INTERFACE_ERC721_METADATA
+            || interface_id == INTERFACE_ERC5192
+    }
+
+    // ====================================
+    //        VIEW FUNCTIONS
+    // ====================================
+
+    /// Get credential metadata
+    /// Returns (kind, reference_id, amount_bucket, issued_at)
+    pub fn get_credential(&self, token_id: U256) -> Result<(u8, u64, u8, u64), CredentialsError> {
+        self.owner_of(token_id)?;
+
+        let credential = self.credentials.get(token_id);
+        Ok((
+            u8::from_le_bytes(credential.kind.get().to_le_bytes()),
+            u64::from_le_bytes(credential.reference_id.get().to_le_bytes()),
+            u8::from_le_bytes(credential.amount_bucket.get().to_le_bytes()),
+            u64::from_le_bytes(credential.issued_at.get().to_le_bytes()),
+        ))
+    }
+}
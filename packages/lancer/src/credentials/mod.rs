@@ -0,0 +1,3 @@
+pub mod credentials;
+
+pub use credentials::LancerCredentials;
@@ -0,0 +1,191 @@
+//!
+//! Lancer Forwarder - Arbitrum Stylus Implementation
+//!
+//! Minimal ERC-2771 forwarder. Users sign a `ForwardRequest` off-chain (EIP-712)
+//! and any relayer submits it through `execute`, paying the gas. The target is
+//! called with the signer's address appended to the calldata, which the Lancer
+//! proxy turns into the contract's `msg_sender()`.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256},
+    call::RawCall,
+    crypto::keccak,
+    prelude::*,
+    evm,
+    msg,
+};
+use crate::signature::{domain_separator, hash_typed_data, recover_packed};
+
+// ====================================
+//          STORAGE STRUCTS
+// ====================================
+
+sol_storage! {
+    #[entrypoint]
+    pub struct MinimalForwarder {
+        mapping(address => uint256) nonces;
+    }
+}
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event RequestExecuted(address indexed from, address indexed to, uint256 nonce, bool success);
+
+    error SignatureMismatch();
+    error InvalidValue();
+    error InsufficientGas();
+}
+
+// ====================================
+//          ERROR TYPES
+// ====================================
+
+#[derive(SolidityError)]
+pub enum ForwarderError {
+    SignatureMismatch(SignatureMismatch),
+    InvalidValue(InvalidValue),
+    InsufficientGas(InsufficientGas),
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+// keccak256("ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,bytes data)")
+const FORWARD_REQUEST_TYPEHASH: FixedBytes<32> =
+    fixed_bytes!("dd8f4b70b0f4393e889bd39128a30628a78b61816a9eb8199759e7a349657e48");
+const DOMAIN_NAME: &str = "MinimalForwarder";
+const DOMAIN_VERSION: &str = "0.0.1";
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+#[public]
+impl MinimalForwarder {
+
+    /// Get the next request nonce of a signer
+    pub fn get_nonce(&self, from: Address) -> U256 {
+        self.nonces.get(from)
+    }
+
+    /// Get the EIP-712 domain separator requests are signed against
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn domain_separator(&self) -> FixedBytes<32> {
+        domain_separator(DOMAIN_NAME, DOMAIN_VERSION)
+    }
+
+    /// Check that a request is signed by `from` and uses its current nonce
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify(
+        &self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: Bytes,
+        signature: Bytes,
+    ) -> bool {
+        if self.nonces.get(from) != nonce {
+            return false;
+        }
+
+        let digest = self.request_digest(from, to, value, gas, nonce, &data);
+        let signer = recover_packed(digest, &signature);
+        signer != Address::ZERO && signer == from
+    }
+
+    /// Execute a signed request, calling `to` with `data` followed by `from`
+    /// Returns whether the call succeeded and its return (or revert) data
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: Bytes,
+        signature: Bytes,
+    ) -> Result<(bool, Bytes), ForwarderError> {
+        if !self.verify(from, to, value, gas, nonce, data.clone(), signature) {
+            return Err(ForwarderError::SignatureMismatch(SignatureMismatch {}));
+        }
+
+        // The relayer must send exactly the value the signer asked for
+        if msg::value() != value {
+            return Err(ForwarderError::InvalidValue(InvalidValue {}));
+        }
+
+        self.nonces.setter(from).set(nonce + U256::from(1));
+
+        // ERC-2771: append the signer to the calldata
+        let mut payload = Vec::with_capacity(data.len() + 20);
+        payload.extend_from_slice(&data);
+        payload.extend_from_slice(from.as_slice());
+
+        let gas_limit = u64::try_from(gas).unwrap_or(u64::MAX);
+        let result = RawCall::new_with_value(value)
+            .gas(gas_limit)
+            .call(to, &payload);
+
+        // A relayer could otherwise make the call fail by forwarding too little gas
+        if evm::gas_left() <= gas_limit / 63 {
+            return Err(ForwarderError::InsufficientGas(InsufficientGas {}));
+        }
+
+        let success = result.is_ok();
+        let return_data = match result {
+            Ok(output) => output,
+            Err(output) => output,
+        };
+
+        evm::log(RequestExecuted {
+            from,
+            to,
+            nonce,
+            success,
+        });
+
+        Ok((success, Bytes::from(return_data)))
+    }
+}
+
+impl MinimalForwarder {
+    /// EIP-712 digest of a forward request
+    fn request_digest(
+        &self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: &[u8],
+    ) -> FixedBytes<32> {
+        let mut encoded = Vec::with_capacity(7 * 32);
+        encoded.extend_from_slice(FORWARD_REQUEST_TYPEHASH.as_slice());
+        encoded.extend_from_slice(from.into_word().as_slice());
+        encoded.extend_from_slice(to.into_word().as_slice());
+        encoded.extend_from_slice(&value.to_be_bytes::<32>());
+        encoded.extend_from_slice(&gas.to_be_bytes::<32>());
+        encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+        encoded.extend_from_slice(keccak(data).as_slice());
+
+        hash_typed_data(domain_separator(DOMAIN_NAME, DOMAIN_VERSION), keccak(&encoded))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod forwarder;
+
+pub use forwarder::MinimalForwarder;
@@ -1,4 +1,13 @@
 #![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
 pub mod protocol;
 // pub mod marketplace;
 // pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
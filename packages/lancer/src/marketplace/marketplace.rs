@@ -13,16 +13,27 @@
 
 extern crate alloc;
 
-use alloc::string::String;
-use alloy_sol_types::sol;
+use alloc::{string::String, vec::Vec};
+use alloy_sol_types::{sol, SolCall};
+use crate::access::{AccessControl, AccessControlError, ADMIN_ROLE, FEE_MANAGER_ROLE, PAUSER_ROLE};
+use crate::metatx::{self, msg_sender};
+use crate::reputation::{self, ReputationScore};
+use crate::signature::{
+    domain_separator, ReputationAttestation, ATTESTATION_DOMAIN_NAME, ATTESTATION_DOMAIN_VERSION,
+    ERC1271_MAGIC_VALUE,
+};
+use crate::upgrade::{self, Migrated, UpgradeError};
 use stylus_sdk::{
-    alloy_primitives::{Address, U256, U64, U8, I8},
+    abi::Bytes,
+    alloy_primitives::{address, Address, FixedBytes, U256, U64, U16, U8},
     prelude::*,
     block,
-    call::Call,
+    call::{self, Call, RawCall},
     contract,
+    crypto::keccak,
     evm,
     msg,
+    types::AddressVM,
 };
 
 // ====================================
@@ -32,31 +43,62 @@ use stylus_sdk::{
 sol_storage! {
     #[entrypoint]
     pub struct Marketplace {
+        // Roles (admin, fee manager, pauser)
+        #[borrow]
+        AccessControl access_control;
+        
         // Immutable state (set once in constructor)
-        address owner;
-        address usdc_token;
+        address usdc_token;  // Default deal token, also used to pay dispute fees to the protocol
+                             // Native ETH is tracked as NATIVE_TOKEN (0xEeee...EEeE)
         address protocol;
         
         // Mutable state
         uint64 deal_id_counter;
-        uint8 fee_percent;
+        uint8 fee_percent;  // Default fee for allowlisted tokens without their own fee
+        bool paused;
         
         // Mappings
         mapping(address => User) users;
         mapping(uint256 => Deal) deals;
         mapping(uint64 => Dispute) disputes;
+        
+        // Layout version this storage was initialized or migrated to.
+        // The contract runs behind a proxy: only append new fields below,
+        // and bump STORAGE_VERSION when the layout or its meaning changes.
+        uint64 storage_version;
+        
+        mapping(address => TokenConfig) tokens;
+        uint64 job_id_counter;
+        mapping(uint64 => Job) jobs;
+        uint64 retainer_id_counter;
+        mapping(uint64 => Retainer) retainers;
+        uint8 tip_fee_percent;  // Fee on tips, 0 makes them fee-exempt
+        address credentials;  // Soulbound credentials minted on completed deals, zero to disable
+        address positions;  // Transferable deal positions minted on acceptance, zero to disable
+        mapping(bytes32 => bool) attestations;  // EIP-712 digests of issued reputation attestations
+        uint8 judge_pool_percent;  // Share of default-token fees routed to the protocol's judge reward pool
+        mapping(address => bytes32) latest_attestations;  // User => digest of their only valid attestation
+        uint64 deal_migration_cursor;  // Next deal `migrate_deals` rewrites for the current layout
     }
     
     pub struct User {
         address user_address;
-        uint256 balance;
-        int8 reputation_as_user;
-        int8 reputation_as_judge;
+        uint256 balance;  // Deprecated, v1 default-token balance folded into balances on first use
+        int8 reputation_as_user;  // Deprecated, superseded by score_as_user
+        int8 reputation_as_judge;  // Deprecated, judge reputation lives in the protocol
         bool is_payer;
         bool is_beneficiary;
-        bool is_judge;
+        bool is_judge;  // Deprecated, judge status lives in the protocol
         mapping(uint256 => uint64) deals;  // Index => dealId
         uint256 deals_count;
+        uint64 tips_count;  // Tips received as a beneficiary
+        mapping(address => uint256) tips_total;  // Token => tipped amount
+        mapping(uint8 => uint64) reputation_events;  // ReputationReason => times applied
+        ReputationScore score_as_user;  // Decaying, see `reputation`
+        uint64 ratings_count;  // Ratings received from counterparties
+        uint64 ratings_sum;
+        mapping(address => uint256) volume;  // Token => deal payouts earned as a beneficiary, net of fees
+        mapping(address => uint256) balances;  // Token => withdrawable amount
     }
     
     pub struct Deal {
@@ -68,6 +110,30 @@ sol_storage! {
         uint64 duration;
         bool accepted;
         bool disputed;
+        address token;
+        bool streaming;  // Escrow vests linearly from started_at over duration
+        uint256 streamed;  // Streaming deals: amount already paid out
+        // Split deals only: beneficiary is the lead, members include the lead
+        mapping(uint256 => address) members;
+        mapping(uint256 => uint16) member_shares;  // Basis points, summing to 10000
+        uint256 members_count;
+        mapping(address => bool) member_accepted;
+        uint256 accepted_count;
+        bool lead_accepts;  // The lead accepts on behalf of the team
+        uint64 parent_id;  // Sub-deals: deal whose escrow funds this one (0 otherwise)
+        uint64 open_children;  // Sub-deals carved from this deal and not settled yet
+        uint8 status;  // DealStatus, closed deals keep their record
+        uint256 closed_at;  // Timestamp of the transition to a final status
+        uint256 final_payout;  // Net amount credited to the beneficiary side (running total for streams)
+        uint8 payer_rating;  // 1-5 given by the payer to the beneficiary side, 0 until rated
+        bytes32 payer_review;  // Content hash of the payer's review
+        uint8 beneficiary_rating;  // 1-5 given by the beneficiary to the payer, 0 until rated
+        bytes32 beneficiary_review;
+        bool has_position;  // The beneficiary's claim is a position token, payouts go to its holder
+        uint64 dispute_id;  // Latest dispute opened over the deal, 0 if never disputed
+        uint256 dispute_fee;  // Fee each side deposits for a requested dispute, in the default token
+        uint256 respondent_deadline;  // The beneficiary must match the fee by then, or lose by default
+        string dispute_proof;  // Requester's proof, forwarded with the case
     }
     
     pub struct Dispute {
@@ -75,6 +141,62 @@ sol_storage! {
         address requester;
         bool is_open;
         bool waiting_for_judges;
+        address respondent;  // The deal's beneficiary, who matches the requester's fee
+        uint256 fee;  // Arbitration fee each side deposits, in the default token
+        bool respondent_paid;  // The respondent's deposit is held by the marketplace
+    }
+    
+    pub struct TokenConfig {
+        bool allowed;
+        bool custom_fee;
+        uint8 fee_percent;
+        uint256 dispute_rate;  // Default-token units per DISPUTE_RATE_UNIT base units, 0 blocks disputes
+    }
+    
+    pub struct Job {
+        uint64 job_id;
+        address payer;
+        address token;
+        uint256 budget;  // Highest price the payer accepts
+        uint64 duration;  // Expected duration in days
+        bytes32 terms_hash;  // Hash of the off-chain job description
+        uint256 deadline;  // Bids are accepted until this timestamp
+        bool open;
+        bool awarded;
+        uint64 deal_id;  // Deal created from the winning bid
+        mapping(uint64 => Bid) bids;
+        uint64 bids_count;
+        mapping(address => uint64) bid_of;  // Bidder => bid index + 1
+        // Sealed-bid reverse auctions only
+        bool sealed;
+        uint256 reveal_deadline;  // Bids can be revealed until this timestamp
+        uint256 bid_bond;  // Locked with each commit, forfeited to the payer if not revealed
+        uint8 reputation_weight;  // Price percent each reputation point is worth (0 = lowest price wins)
+    }
+    
+    pub struct Bid {
+        address bidder;
+        uint256 price;
+        uint64 duration;  // Proposed duration in days
+        bytes32 proposal_hash;  // Hash of the off-chain proposal
+        bool withdrawn;
+        bytes32 commitment;  // Sealed bids: keccak256(job_id || bidder || price || secret)
+        bool revealed;
+    }
+    
+    pub struct Retainer {
+        address payer;
+        address beneficiary;
+        address token;
+        uint256 amount_per_period;
+        uint64 period_days;
+        uint64 periods;  // Funded periods, lowered when the payer stops the retainer
+        uint64 notice_periods;  // Periods still paid after the current one when stopped
+        uint64 claimed_periods;  // Periods before this index are settled
+        uint256 started_at;
+        bool accepted;
+        bool stopped;
+        mapping(uint64 => uint64) period_deals;  // Period => deal it was moved to for a dispute
     }
 }
 
@@ -84,18 +206,68 @@ sol_storage! {
 
 sol! {
     event UserRegistered(address indexed user, bool is_payer, bool is_beneficiary, bool is_judge);
-    event DealCreated(uint64 indexed deal_id, address indexed payer, address indexed beneficiary, uint256 amount);
+    event DealCreated(uint64 indexed deal_id, address indexed payer, address indexed beneficiary, address token, uint256 amount);
     event DealAccepted(uint64 indexed deal_id);
     event DealRejected(uint64 indexed deal_id);
     event DisputeRequested(uint256 indexed dispute_id, address indexed requester);
-    event UserWithdrew(address indexed user, uint256 amount);
+    event UserWithdrew(address indexed user, address indexed token, uint256 amount);
     event PaymentDeposited(address indexed user, uint256 amount);
     event DealAmountUpdated(uint64 indexed deal_id, uint256 new_amount);
     event DealFinalized(uint64 indexed deal_id);
     event DealDurationUpdated(uint64 indexed deal_id, uint16 new_duration);
     event DisputeCreated(uint64 indexed deal_id, address indexed requester);
     event DisputeResolved(uint64 indexed dispute_id, address indexed winner);
+    event DisputeFeeDeposited(uint64 indexed dispute_id, address indexed party, uint256 amount);
+    event DisputeFeeRefunded(uint64 indexed dispute_id, address indexed party, uint256 amount);
+    event PendingDisputeFeeDeposited(uint64 indexed deal_id, address indexed party, uint256 amount);
+    event PendingDisputeFeeRefunded(uint64 indexed deal_id, address indexed party, uint256 amount);
+    event DefaultJudgment(uint64 indexed deal_id, address indexed winner);
     event NewFeePercent(uint8 new_fee_percent);
+    event NewTipFeePercent(uint8 new_tip_fee_percent);
+    event NewJudgePoolPercent(uint8 new_judge_pool_percent);
+    event JudgePoolFunded(address indexed token, uint256 amount);
+    event TipSent(uint64 indexed deal_id, address indexed payer, address indexed beneficiary, address token, uint256 amount, uint256 fee);
+    event ReputationChanged(address indexed user, uint64 indexed deal_id, uint8 reason, int64 points, int128 reputation);
+    event CredentialsUpdated(address credentials);
+    event PositionsUpdated(address positions);
+    event ReputationAttested(
+        address indexed user,
+        bytes32 indexed digest,
+        uint64 completed_deals,
+        uint256 volume,
+        uint64 disputes_won,
+        uint64 disputes_lost,
+        uint64 ratings_count,
+        uint64 rating_average,
+        int128 reputation,
+        uint64 issued_at
+    );
+    event DealRated(uint64 indexed deal_id, address indexed rater, address indexed rated, uint8 rating, bytes32 review_hash);
+    event TokenAllowed(address indexed token, bool allowed);
+    event TokenFeePercentUpdated(address indexed token, uint8 fee_percent);
+    event TokenDisputeRateUpdated(address indexed token, uint256 rate);
+    event Paused(address account);
+    event Unpaused(address account);
+    event JobPosted(uint64 indexed job_id, address indexed payer, address token, uint256 budget, uint256 deadline);
+    event JobCancelled(uint64 indexed job_id);
+    event JobAwarded(uint64 indexed job_id, uint64 bid_index, uint64 indexed deal_id);
+    event BidSubmitted(uint64 indexed job_id, uint64 bid_index, address indexed bidder, uint256 price);
+    event BidWithdrawn(uint64 indexed job_id, uint64 bid_index);
+    event SealedJobPosted(uint64 indexed job_id, uint256 reveal_deadline, uint256 bid_bond, uint8 reputation_weight);
+    event BidCommitted(uint64 indexed job_id, uint64 bid_index, address indexed bidder);
+    event BidRevealed(uint64 indexed job_id, uint64 bid_index, uint256 price);
+    event BidBondForfeited(uint64 indexed job_id, uint64 bid_index, uint256 amount);
+    event RetainerCreated(uint64 indexed retainer_id, address indexed payer, address indexed beneficiary, address token, uint256 amount_per_period, uint64 periods);
+    event RetainerAccepted(uint64 indexed retainer_id);
+    event RetainerClaimed(uint64 indexed retainer_id, uint64 periods, uint256 payout);
+    event RetainerStopped(uint64 indexed retainer_id, uint64 end_period, uint256 refund);
+    event RetainerPeriodDisputed(uint64 indexed retainer_id, uint64 period, uint64 indexed deal_id);
+    event SplitDealCreated(uint64 indexed deal_id, address[] members, uint16[] shares_bps, bool lead_accepts);
+    event SplitMemberAccepted(uint64 indexed deal_id, address indexed member);
+    event SubDealCreated(uint64 indexed parent_id, uint64 indexed deal_id, address indexed beneficiary, uint256 amount);
+    event StreamCreated(uint64 indexed deal_id);
+    event StreamWithdrawn(uint64 indexed deal_id, uint256 amount, uint256 payout);
+    event StreamCancelled(uint64 indexed deal_id, uint256 vested, uint256 refund);
     
     error Unauthorized();
     error NotFound();
@@ -104,6 +276,8 @@ sol! {
     error InvalidState();
     error InsufficientBalance();
     error CallFailed();
+    error ContractPaused();
+    error ContractNotPaused();
 }
 
 // ====================================
@@ -119,6 +293,8 @@ pub enum MarketplaceError {
     InvalidState(InvalidState),
     InsufficientBalance(InsufficientBalance),
     CallFailed(CallFailed),
+    ContractPaused(ContractPaused),
+    ContractNotPaused(ContractNotPaused),
 }
 
 // Implement From for stylus_sdk::call::Error
@@ -128,29 +304,184 @@ impl From<stylus_sdk::call::Error> for MarketplaceError {
     }
 }
 
+// Missing roles surface as Unauthorized
+impl From<AccessControlError> for MarketplaceError {
+    fn from(_error: AccessControlError) -> Self {
+        MarketplaceError::Unauthorized(Unauthorized {})
+    }
+}
+
+impl From<UpgradeError> for MarketplaceError {
+    fn from(error: UpgradeError) -> Self {
+        match error {
+            UpgradeError::NotDelegated(_) => MarketplaceError::InvalidState(InvalidState {}),
+            _ => MarketplaceError::InvalidInput(InvalidInput {}),
+        }
+    }
+}
+
 // ====================================
 //        CONSTANTS          
 // ====================================
 
 const USDC_DECIMALS: u8 = 6;
+const STORAGE_VERSION: u64 = 4;
+// Token key used for ETH balances and deals, v1 deals store a zero token meaning the default token
+const NATIVE_TOKEN: Address = address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
+const MAX_SEALED_BIDS: u64 = 50; // Bounds the loop that settles a sealed auction
+const MAX_SPLIT_MEMBERS: usize = 20; // Bounds the loop that pays a split deal
+const BPS_DENOMINATOR: u64 = 10_000;
+const MAX_PAGE_SIZE: u64 = 100; // Bounds the deals returned by get_user_deals
+const MAX_MIGRATION_BATCH: u64 = 500; // Bounds the deals rewritten by one migrate_deals call
+
+const DEAL_STATUS_ANY: u8 = u8::MAX; // get_user_deals filter matching every status
+const CREDENTIAL_COMPLETED_DEAL: u8 = 0; // Credential kind, see `credentials`
 const ONE_DAY: U256 = U256::from_limbs([86400u64, 0, 0, 0]); // 86400 seconds
 const ONE_WEEK: U256 = U256::from_limbs([604800u64, 0, 0, 0]); // 7 days in seconds
+const RESPONDENT_DEPOSIT_WINDOW: U256 = ONE_WEEK; // Time the beneficiary has to match a dispute fee
+const DISPUTE_RATE_UNIT: u64 = 1_000_000_000_000_000_000; // Token base units a dispute rate is quoted for
+
+/// Lifecycle of a deal, stored as a uint8 in `Deal::status`
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DealStatus {
+    Proposed,   // Waiting for the beneficiary to accept
+    Accepted,   // Running
+    Completed,  // Finished by the payer, or a stream fully withdrawn
+    Rejected,   // Withdrawn by the payer before acceptance
+    Disputed,   // Waiting for the protocol's judges
+    Resolved,   // Settled by a dispute result
+    Cancelled,  // Stream stopped by the payer
+    Expired,    // Paid to the beneficiary after the payer's review window lapsed
+}
+
+impl DealStatus {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => DealStatus::Accepted,
+            2 => DealStatus::Completed,
+            3 => DealStatus::Rejected,
+            4 => DealStatus::Disputed,
+            5 => DealStatus::Resolved,
+            6 => DealStatus::Cancelled,
+            7 => DealStatus::Expired,
+            _ => DealStatus::Proposed,
+        }
+    }
+    
+    /// Whether the deal still holds escrow
+    fn is_open(self) -> bool {
+        matches!(self, DealStatus::Proposed | DealStatus::Accepted | DealStatus::Disputed)
+    }
+    
+    /// Whether the deal paid out to its beneficiary side (tips are allowed)
+    fn is_settled(self) -> bool {
+        !self.is_open() && self != DealStatus::Rejected
+    }
+}
+
+impl Deal {
+    fn status(&self) -> DealStatus {
+        DealStatus::from_u8(u8::from_le_bytes(self.status.get().to_le_bytes()))
+    }
+    
+    /// Whether the deal exists and still holds escrow
+    fn is_open(&self) -> bool {
+        self.deal_id.get() != U64::ZERO && self.status().is_open()
+    }
+}
+
+/// Why a user's `score_as_user` changed, each reason carries fixed points
+///
+/// Scoring model, applied when a deal reaches a final status (the team is the
+/// beneficiary, or every member of a split deal):
+/// - Completed by the payer: payer and team +1 (`DealCompleted`), team +1 more
+///   when finished within the deal duration (`DeliveredOnTime`). Fully withdrawn
+///   streams count as completed on time.
+/// - Expired (the beneficiary claimed after the review window): team +1
+///   (`DealCompleted`), payer -2 (`DealExpired`).
+/// - Rejected by the payer before acceptance: payer -1 (`DealRejected`).
+/// - Resolved by a dispute: winning side +1 (`DisputeWon`), losing side -3 (`DisputeLost`).
+/// - Cancelled streams leave reputation unchanged.
+///
+/// Points are added to a fixed-point score that decays toward zero over time
+/// (see `reputation`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ReputationReason {
+    DealCompleted,
+    DeliveredOnTime,
+    DealExpired,
+    DealRejected,
+    DisputeWon,
+    DisputeLost,
+}
+
+impl ReputationReason {
+    fn points(self) -> i64 {
+        match self {
+            ReputationReason::DealCompleted => 1,
+            ReputationReason::DeliveredOnTime => 1,
+            ReputationReason::DealExpired => -2,
+            ReputationReason::DealRejected => -1,
+            ReputationReason::DisputeWon => 1,
+            ReputationReason::DisputeLost => -3,
+        }
+    }
+}
 
 // ====================================
-//      EXTERNAL INTERFACE CALLS          
+//      EXTERNAL INTERFACE CALLS
 // ====================================
 
+// EIP-2612 extension, kept apart since its generated binding exceeds clippy's argument limit
+#[allow(clippy::too_many_arguments)]
+mod permit {
+    extern crate alloc;
+    use stylus_sdk::prelude::*;
+
+    sol_interface! {
+        interface IERC20Permit {
+            function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+        }
+    }
+}
+use permit::IERC20Permit;
+
+// Raw ERC-20 calls for `safe_transfer`, which must accept tokens returning nothing
+mod erc20 {
+    alloy_sol_types::sol! {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    }
+}
+
 sol_interface! {
     interface IERC20 {
         function transferFrom(address from, address to, uint256 amount) external returns (bool);
         function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
     }
     
     interface IProtocol {
-        function createDispute(uint64 deal_id, address requester, string calldata proof) external;
+        function createDispute(uint64 deal_id, address requester, string calldata proof, uint256 fee) external returns (uint64);
         function updateDisputeForPayer(uint64 dispute_id, address payer, string calldata proof) external;
         function updateDisputeForBeneficiary(uint64 dispute_id, address beneficiary, string calldata proof) external;
         function executeDisputeResult(uint64 dispute_id) external returns (bool);
+        function registerJudgeFor(address judge) external;
+        function fundRewardPool(uint256 amount) external;
+        function arbitrationCost(uint256 disputed_value) external view returns (uint256);
+        function getJudge(address judge_address) external view returns (address, uint256, int128, uint64, uint64, uint64);
+    }
+    
+    interface ILancerCredentials {
+        function mint(address to, uint8 kind, uint64 reference_id, uint8 amount_bucket) external returns (uint256);
+    }
+    
+    interface ILancerPositions {
+        function mint(address to, uint256 deal_id) external;
+        function burn(uint256 deal_id) external;
+        function ownerOf(uint256 token_id) external view returns (address);
     }
 }
 
@@ -159,6 +490,7 @@ sol_interface! {
 // ====================================
 
 #[public]
+#[inherit(AccessControl)]
 impl Marketplace {
     
     // ====================================
@@ -166,6 +498,7 @@ impl Marketplace {
     // ====================================
     
     /// Initialize the marketplace contract
+    /// `owner` receives the admin, fee manager and pauser roles
     pub fn init(
         &mut self,
         owner: Address,
@@ -173,22 +506,40 @@ impl Marketplace {
         token: Address,
         protocol_address: Address,
     ) -> Result<(), MarketplaceError> {
-        self.owner.set(owner);
+        // Can only be initialized once (directly or through the proxy)
+        if self.storage_version.get() != U64::ZERO {
+            return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+        }
+        
+        if fee_percent > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.storage_version.set(U64::from(STORAGE_VERSION));
+        self.access_control._grant_role(ADMIN_ROLE, owner);
+        self.access_control._grant_role(FEE_MANAGER_ROLE, owner);
+        self.access_control._grant_role(PAUSER_ROLE, owner);
         self.fee_percent.set(U8::from(fee_percent));
         self.usdc_token.set(token);
+        self.tokens.setter(token).allowed.set(true);
+        self.tokens.setter(NATIVE_TOKEN).allowed.set(true);
         self.protocol.set(protocol_address);
         self.deal_id_counter.set(U64::from(1));
+        self.job_id_counter.set(U64::from(1));
+        self.retainer_id_counter.set(U64::from(1));
         Ok(())
     }
     
     // ====================================
-    //        ONLY-OWNER FUNCTIONS          
+    //        ROLE-GATED FUNCTIONS          
     // ====================================
     
-    /// Update the fee percentage
+    /// Update the default fee percentage (only fee manager)
     pub fn set_fee_percent(&mut self, new_fee_percent: u8) -> Result<(), MarketplaceError> {
-        if msg::sender() != self.owner.get() {
-            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        self.access_control.only_role(FEE_MANAGER_ROLE, msg_sender())?;
+        
+        if new_fee_percent > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
         }
         
         self.fee_percent.set(U8::from(new_fee_percent));
@@ -197,6 +548,261 @@ impl Marketplace {
         Ok(())
     }
     
+    /// Set the fee percentage charged on tips, 0 for fee-exempt tips (only fee manager)
+    pub fn set_tip_fee_percent(&mut self, new_tip_fee_percent: u8) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(FEE_MANAGER_ROLE, msg_sender())?;
+        
+        if new_tip_fee_percent > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.tip_fee_percent.set(U8::from(new_tip_fee_percent));
+        evm::log(NewTipFeePercent { new_tip_fee_percent });
+        
+        Ok(())
+    }
+    
+    /// Set the share of fees routed to the protocol's judge reward pool, in percent (only fee manager)
+    /// Only fees in the default token are shared, the pool pays judges in it
+    pub fn set_judge_pool_percent(&mut self, new_judge_pool_percent: u8) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(FEE_MANAGER_ROLE, msg_sender())?;
+        
+        if new_judge_pool_percent > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.judge_pool_percent.set(U8::from(new_judge_pool_percent));
+        evm::log(NewJudgePoolPercent { new_judge_pool_percent });
+        
+        Ok(())
+    }
+    
+    /// Set the fee percentage charged on deals paid with `token` (only fee manager)
+    pub fn set_token_fee_percent(&mut self, token: Address, fee_percent: u8) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(FEE_MANAGER_ROLE, msg_sender())?;
+        
+        if fee_percent > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let mut config = self.tokens.setter(token);
+        if !config.allowed.get() {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        config.custom_fee.set(true);
+        config.fee_percent.set(U8::from(fee_percent));
+        
+        evm::log(TokenFeePercentUpdated { token, fee_percent });
+        
+        Ok(())
+    }
+    
+    /// Set the value of `token` in the default token, used to price disputes over its deals
+    /// (only fee manager). `rate` is in default-token units per 10^18 base units of `token`,
+    /// 0 rejects new disputes over its deals
+    pub fn set_token_dispute_rate(&mut self, token: Address, rate: U256) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(FEE_MANAGER_ROLE, msg_sender())?;
+        
+        // Deals in removed tokens can still be disputed, so any token may be priced
+        self.tokens.setter(token).dispute_rate.set(rate);
+        
+        evm::log(TokenDisputeRateUpdated { token, rate });
+        
+        Ok(())
+    }
+    
+    /// Add or remove a token from the deal allowlist (only admin)
+    /// Use NATIVE_TOKEN (0xEeee...EEeE) for native ETH. Removing a token only blocks new deals,
+    /// existing deals settle normally
+    pub fn set_token_allowed(&mut self, token: Address, allowed: bool) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        self.tokens.setter(token).allowed.set(allowed);
+        evm::log(TokenAllowed { token, allowed });
+        
+        Ok(())
+    }
+    
+    /// Set the credentials collection beneficiaries receive on completed deals, zero to disable (only admin)
+    pub fn set_credentials(&mut self, credentials: Address) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        self.credentials.set(credentials);
+        evm::log(CredentialsUpdated { credentials });
+        
+        Ok(())
+    }
+    
+    /// Get the credentials collection
+    pub fn credentials(&self) -> Address {
+        self.credentials.get()
+    }
+    
+    /// Set the collection minting deal positions on acceptance, zero to disable (only admin)
+    /// Deals accepted while disabled keep paying their stored beneficiary
+    pub fn set_positions(&mut self, positions: Address) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        self.positions.set(positions);
+        evm::log(PositionsUpdated { positions });
+        
+        Ok(())
+    }
+    
+    /// Get the deal positions collection
+    pub fn positions(&self) -> Address {
+        self.positions.get()
+    }
+    
+    /// Pause deal creation, acceptance and disputes (only pauser)
+    pub fn pause(&mut self) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(PAUSER_ROLE, msg_sender())?;
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        self.paused.set(true);
+        evm::log(Paused { account: msg_sender() });
+        
+        Ok(())
+    }
+    
+    /// Resume normal operation (only pauser)
+    pub fn unpause(&mut self) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(PAUSER_ROLE, msg_sender())?;
+        
+        if !self.paused.get() {
+            return Err(MarketplaceError::ContractNotPaused(ContractNotPaused {}));
+        }
+        
+        self.paused.set(false);
+        evm::log(Unpaused { account: msg_sender() });
+        
+        Ok(())
+    }
+    
+    // ====================================
+    //            UPGRADES          
+    // ====================================
+    
+    /// Switch the proxy to a new implementation (only admin, through the proxy)
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), MarketplaceError> {
+        self.authorize_upgrade(new_implementation)?;
+        
+        let current_version = u64::from_le_bytes(self.storage_version.get().to_le_bytes());
+        upgrade::upgrade_to(self, current_version, new_implementation)?;
+        
+        Ok(())
+    }
+    
+    /// Bring storage up to STORAGE_VERSION after an upgrade (only admin)
+    pub fn migrate(&mut self) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        let from_version = u64::from_le_bytes(self.storage_version.get().to_le_bytes());
+        if from_version == 0 || from_version >= STORAGE_VERSION {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Deal records are rewritten beforehand, in batches (see `migrate_deals`)
+        if self.pending_deal_migrations() != 0 {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Migration steps are applied in order, one block per version bump
+        
+        // v2: per-token balances. Users can't be enumerated here, so v1 balances
+        // are folded into `balances` lazily (see `settle_legacy_balance`)
+
+        // v3: deal tokens and allowlist. v1 deals carry no token and settle in
+        // the default token, which also must be allowlisted as `init` does
+        if from_version < 3 {
+            let usdc = self.usdc_token.get();
+            self.tokens.setter(usdc).allowed.set(true);
+        }
+        
+        // v4: deal status, set on each deal record by `migrate_deals`
+        
+        self.storage_version.set(U64::from(STORAGE_VERSION));
+        self.deal_migration_cursor.set(U64::ZERO);
+        evm::log(Migrated {
+            from_version,
+            to_version: STORAGE_VERSION,
+        });
+        
+        Ok(())
+    }
+    
+    /// Rewrite up to `count` deals for the current layout, resuming where the last batch
+    /// stopped (only admin). Call until no deals remain, then `migrate`.
+    /// Returns the number of deals still to rewrite
+    pub fn migrate_deals(&mut self, count: u64) -> Result<u64, MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        let from_version = u64::from_le_bytes(self.storage_version.get().to_le_bytes());
+        if from_version == 0 || from_version >= STORAGE_VERSION {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        if count == 0 || count > MAX_MIGRATION_BATCH {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let deal_count = u64::from_le_bytes(self.deal_id_counter.get().to_le_bytes());
+        let start = u64::from_le_bytes(self.deal_migration_cursor.get().to_le_bytes()).max(1);
+        let end = start.saturating_add(count).min(deal_count);
+        for deal_id in start..end {
+            self.migrate_deal(deal_id, from_version);
+        }
+        self.deal_migration_cursor.set(U64::from(end.max(start)));
+        
+        Ok(self.pending_deal_migrations())
+    }
+    
+    /// ERC-1822 proxiable UUID (the EIP-1967 implementation slot)
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> FixedBytes<32> {
+        upgrade::proxiable_uuid()
+    }
+    
+    /// Storage layout version implemented by this code
+    pub fn layout_version() -> u64 {
+        STORAGE_VERSION
+    }
+    
+    /// Storage layout version recorded in storage
+    pub fn storage_version(&self) -> u64 {
+        u64::from_le_bytes(self.storage_version.get().to_le_bytes())
+    }
+    
+    /// Current implementation behind the proxy (zero when called directly)
+    pub fn implementation(&self) -> Address {
+        upgrade::implementation()
+    }
+    
+    // ====================================
+    //         META-TRANSACTIONS
+    // ====================================
+    
+    /// Set the ERC-2771 trusted forwarder, the zero address disables it (only admin)
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        metatx::set_trusted_forwarder(forwarder);
+        Ok(())
+    }
+    
+    /// Get the trusted forwarder
+    pub fn trusted_forwarder(&self) -> Address {
+        metatx::trusted_forwarder()
+    }
+    
+    /// Whether calls from `forwarder` carry a trusted sender (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        metatx::is_trusted_forwarder(forwarder)
+    }
+    
     // ====================================
     //         EXTERNAL FUNCTIONS          
     // ====================================
@@ -208,7 +814,7 @@ impl Marketplace {
         is_beneficiary: bool,
         is_judge: bool,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let mut user = self.users.setter(sender);
         
         // Check if user already registered
@@ -223,14 +829,15 @@ impl Marketplace {
         
         // Set user data
         user.user_address.set(sender);
-        user.balance.set(U256::ZERO);
-        user.reputation_as_user.set(I8::ZERO);
-        user.reputation_as_judge.set(I8::ZERO);
         user.is_payer.set(is_payer);
         user.is_beneficiary.set(is_beneficiary);
-        user.is_judge.set(is_judge);
         user.deals_count.set(U256::ZERO);
         
+        // Judges are registered with the protocol, which owns their status and reputation
+        if is_judge {
+            self.register_judge(sender)?;
+        }
+        
         evm::log(UserRegistered {
             user: sender,
             is_payer,
@@ -248,7 +855,7 @@ impl Marketplace {
         is_beneficiary: bool,
         is_judge: bool,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let mut user = self.users.setter(sender);
         
         // Check if user is registered
@@ -269,7 +876,7 @@ impl Marketplace {
             user.is_beneficiary.set(true);
         }
         if is_judge {
-            user.is_judge.set(true);
+            self.register_judge(sender)?;
         }
         
         evm::log(UserRegistered {
@@ -282,7 +889,7 @@ impl Marketplace {
         Ok(())
     }
     
-    /// Create a new deal
+    /// Create a new deal paid in the default token
     /// Sender (payer/buyer) creates a deal with a beneficiary (seller)
     pub fn create_deal(
         &mut self,
@@ -290,80 +897,210 @@ impl Marketplace {
         amount: U256,
         duration: u64,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let token = self.usdc_token.get();
+        self.create_deal_with_token(beneficiary, token, amount, duration)
+    }
+    
+    /// Create a new deal paid in any allowlisted ERC-20 token
+    pub fn create_deal_with_token(
+        &mut self,
+        beneficiary: Address,
+        token: Address,
+        amount: U256,
+        duration: u64,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
         
-        // Validate inputs
-        if amount == U256::ZERO {
-            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
-        }
-        if beneficiary == Address::ZERO {
+        // ETH deals must go through create_deal_with_eth
+        if token == NATIVE_TOKEN {
             return Err(MarketplaceError::InvalidInput(InvalidInput {}));
         }
         
-        // Check user roles - sender must be a payer (buyer)
-        let sender_user = self.users.get(sender);
-        if !sender_user.is_payer.get() {
-            return Err(MarketplaceError::InvalidState(InvalidState {}));
-        }
-        
-        // Beneficiary must be registered as beneficiary (seller)
-        let beneficiary_user = self.users.get(beneficiary);
-        if !beneficiary_user.is_beneficiary.get() {
-            return Err(MarketplaceError::InvalidState(InvalidState {}));
-        }
+        self.validate_new_deal(sender, beneficiary, token, amount)?;
         
-        // Transfer USDC from payer to marketplace contract
-        let token_address = self.usdc_token.get();
-        let config = Call::new_in(self);
-        let token = IERC20::new(token_address);
-        let contract_addr = contract::address();
+        // Transfer deal tokens from payer to marketplace contract
+        self.deposit(sender, token, amount)?;
         
-        // Transfer tokens from sender (payer) to this contract
-        match token.transfer_from(config, sender, contract_addr, amount) {
-            Ok(_) => {},
-            Err(_) => return Err(MarketplaceError::CallFailed(CallFailed {})),
-        }
-        
-        // Get current deal ID
-        let deal_id = self.deal_id_counter.get();
-        
-        // Create deal - sender is payer, beneficiary is the seller
-        let mut deal = self.deals.setter(U256::from(deal_id));
-        deal.deal_id.set(U64::from(deal_id));
-        deal.payer.set(sender);  // Sender is the payer (buyer)
-        deal.beneficiary.set(beneficiary);  // Parameter is beneficiary (seller)
-        deal.amount.set(amount);
-        deal.duration.set(U64::from(duration));
-        deal.started_at.set(U256::ZERO);
-        deal.accepted.set(false);
-        deal.disputed.set(false);
+        self.record_deal(sender, beneficiary, token, amount, duration);
         
-        let deal_id_u64 = u64::from_le_bytes(deal_id.to_le_bytes());
-        evm::log(DealCreated {
-            deal_id: deal_id_u64,
-            payer: sender,  // Sender is the payer
-            beneficiary,  // Beneficiary is the seller
-            amount,
-        });
+        Ok(())
+    }
+    
+    /// Create a new deal paid in native ETH, escrowing `msg::value()`
+    #[payable]
+    pub fn create_deal_with_eth(
+        &mut self,
+        beneficiary: Address,
+        duration: u64,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let amount = msg::value();
         
-        // Increment counter
-        let current_counter = self.deal_id_counter.get();
-        self.deal_id_counter.set(current_counter + U64::from(1));
+        self.validate_new_deal(sender, beneficiary, NATIVE_TOKEN, amount)?;
+        self.record_deal(sender, beneficiary, NATIVE_TOKEN, amount, duration);
         
         Ok(())
     }
     
-    /// Update deal amount (only before acceptance)
-    pub fn update_deal_amount(
+    /// Create a new ERC-20 deal, approving the escrow with an EIP-2612 signature
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_deal_with_permit(
         &mut self,
-        deal_id: u64,
-        new_amount: U256,
+        beneficiary: Address,
+        token: Address,
+        amount: U256,
+        duration: u64,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
-        let mut deal = self.deals.setter(U256::from(deal_id));
+        self.try_permit(token, msg_sender(), amount, deadline, v, r, s);
+        self.create_deal_with_token(beneficiary, token, amount, duration)
+    }
+    
+    /// Create a deal shared by a team of beneficiaries
+    /// `beneficiaries[0]` is the lead, `shares_bps` must add up to 10000. Every member
+    /// must accept unless `lead_accepts` lets the lead accept for the team. Payouts are
+    /// credited pro-rata (send the amount as value for ETH deals).
+    #[payable]
+    pub fn create_split_deal(
+        &mut self,
+        beneficiaries: Vec<Address>,
+        shares_bps: Vec<u16>,
+        token: Address,
+        amount: U256,
+        duration: u64,
+        lead_accepts: bool,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
+        // Validate the team
+        if beneficiaries.len() < 2
+            || beneficiaries.len() > MAX_SPLIT_MEMBERS
+            || beneficiaries.len() != shares_bps.len()
+        {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        let total_bps: u64 = shares_bps.iter().map(|share| *share as u64).sum();
+        if total_bps != BPS_DENOMINATOR || shares_bps.contains(&0) {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        for (i, member) in beneficiaries.iter().enumerate() {
+            if *member == sender || beneficiaries[..i].contains(member) {
+                return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+            }
+            if !self.users.get(*member).is_beneficiary.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+        }
+        
+        let lead = beneficiaries[0];
+        self.validate_new_deal(sender, lead, token, amount)?;
+        self.deposit(sender, token, amount)?;
+        
+        let deal_id = self.record_deal(sender, lead, token, amount, duration);
+        
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        for (i, (member, share)) in beneficiaries.iter().zip(shares_bps.iter()).enumerate() {
+            deal.members.setter(U256::from(i)).set(*member);
+            deal.member_shares.setter(U256::from(i)).set(U16::from(*share));
+        }
+        deal.members_count.set(U256::from(beneficiaries.len()));
+        deal.lead_accepts.set(lead_accepts);
+        
+        // The lead was indexed by record_deal
+        for member in beneficiaries.iter().skip(1) {
+            self.index_deal(*member, deal_id);
+        }
+        
+        evm::log(SplitDealCreated {
+            deal_id,
+            members: beneficiaries,
+            shares_bps,
+            lead_accepts,
+        });
+        
+        Ok(deal_id)
+    }
+    
+    /// Subcontract part of an accepted deal (only the holder of its payout claim,
+    /// the beneficiary unless the deal's position was transferred)
+    /// The sub-deal is funded from the parent's escrow, reducing the parent payout.
+    /// The caller is the sub-deal's payer and the parent can only be settled once
+    /// every sub-deal is.
+    pub fn create_sub_deal(
+        &mut self,
+        parent_id: u64,
+        beneficiary: Address,
+        amount: U256,
+        duration: u64,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        let (token, parent_amount, open_children) = {
+            let parent = self.deals.get(U256::from(parent_id));
+            
+            // Check deal exists and is still open
+            if !parent.is_open() {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            let holder = self.claim_holder(parent_id, parent.beneficiary.get());
+            
+            // Streams and team deals pay out on their own schedule and shares
+            let eligible = parent.accepted.get()
+                && !parent.disputed.get()
+                && !parent.streaming.get()
+                && parent.members_count.get() == U256::ZERO;
+            check_sub_deal_parent(sender, holder, eligible)?;
+            
+            (parent.token.get(), parent.amount.get(), parent.open_children.get())
+        };
+        
+        // Validate inputs, the parent must keep part of its escrow
+        if amount == U256::ZERO || amount >= parent_amount {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if beneficiary == Address::ZERO || beneficiary == sender {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if !self.users.get(beneficiary).is_beneficiary.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let mut parent = self.deals.setter(U256::from(parent_id));
+        parent.amount.set(parent_amount - amount);
+        parent.open_children.set(open_children + U64::from(1));
+        
+        let deal_id = self.record_deal(sender, beneficiary, token, amount, duration);
+        self.deals.setter(U256::from(deal_id)).parent_id.set(U64::from(parent_id));
+        
+        evm::log(SubDealCreated {
+            parent_id,
+            deal_id,
+            beneficiary,
+            amount,
+        });
+        
+        Ok(deal_id)
+    }
+    
+    /// Update deal amount (only before acceptance)
+    pub fn update_deal_amount(
+        &mut self,
+        deal_id: u64,
+        new_amount: U256,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        
+        // Check deal exists and is still open
+        if !deal.is_open() {
             return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
@@ -382,6 +1119,11 @@ impl Marketplace {
             return Err(MarketplaceError::InvalidInput(InvalidInput {}));
         }
         
+        // Sub-deal escrow is carved from the parent and cannot change
+        if deal.parent_id.get() != U64::ZERO {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
         deal.amount.set(new_amount);
         
         evm::log(DealAmountUpdated {
@@ -398,11 +1140,11 @@ impl Marketplace {
         deal_id: u64,
         new_duration: u16,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let mut deal = self.deals.setter(U256::from(deal_id));
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
+        // Check deal exists and is still open
+        if !deal.is_open() {
             return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
@@ -435,17 +1177,16 @@ impl Marketplace {
     /// Accept a deal (only beneficiary can accept)
     /// USDC was already transferred when deal was created, so just mark as accepted
     pub fn accept_deal(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
-        let mut deal = self.deals.setter(U256::from(deal_id));
+        let sender = msg_sender();
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
-            return Err(MarketplaceError::NotFound(NotFound {}));
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
         }
+        let deal = self.deals.get(U256::from(deal_id));
         
-        // Only beneficiary (seller) can accept
-        if sender != deal.beneficiary.get() {
-            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        // Check deal exists and is still open
+        if !deal.is_open() {
+            return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
         // Check not already accepted
@@ -453,22 +1194,56 @@ impl Marketplace {
             return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
         }
         
-        // Mark as accepted and set start time
-        deal.accepted.set(true);
-        deal.started_at.set(U256::from(block::timestamp()));
+        // Split deals start once every member accepted, or the lead if allowed
+        let members_count = deal.members_count.get();
+        if members_count != U256::ZERO && !(deal.lead_accepts.get() && sender == deal.beneficiary.get()) {
+            let mut is_member = false;
+            for i in 0..members_count.as_limbs()[0] {
+                if deal.members.get(U256::from(i)) == sender {
+                    is_member = true;
+                    break;
+                }
+            }
+            if !is_member {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            if deal.member_accepted.get(sender) {
+                return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+            }
+            
+            let accepted_count = deal.accepted_count.get() + U256::from(1);
+            let mut deal_mut = self.deals.setter(U256::from(deal_id));
+            deal_mut.member_accepted.setter(sender).set(true);
+            deal_mut.accepted_count.set(accepted_count);
+            
+            evm::log(SplitMemberAccepted {
+                deal_id,
+                member: sender,
+            });
+            
+            if accepted_count == members_count {
+                self.start_deal(deal_id);
+            }
+            return Ok(());
+        }
         
-        evm::log(DealAccepted { deal_id });
+        // Only beneficiary (seller) can accept
+        if sender != deal.beneficiary.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        self.start_deal(deal_id);
         
         Ok(())
     }
     
     /// Reject a deal (only before acceptance)
     pub fn reject_deal(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let deal = self.deals.get(U256::from(deal_id));
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
+        // Check deal exists and is still open
+        if !deal.is_open() {
             return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
@@ -482,16 +1257,17 @@ impl Marketplace {
             return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
         }
         
-        // Delete deal (reset to default values)
-        let mut deal_mut = self.deals.setter(U256::from(deal_id));
-        deal_mut.deal_id.set(U64::ZERO);
-        deal_mut.payer.set(Address::ZERO);
-        deal_mut.beneficiary.set(Address::ZERO);
-        deal_mut.amount.set(U256::ZERO);
-        deal_mut.duration.set(U64::ZERO);
-        deal_mut.started_at.set(U256::ZERO);
-        deal_mut.accepted.set(false);
-        deal_mut.disputed.set(false);
+        // Rejected sub-deals give their escrow back to the parent
+        let parent_id = deal.parent_id.get();
+        let amount = deal.amount.get();
+        if parent_id != U64::ZERO {
+            let mut parent = self.deals.setter(U256::from(parent_id));
+            let parent_amount = parent.amount.get();
+            parent.amount.set(parent_amount + amount);
+        }
+        
+        self.close_deal(deal_id, DealStatus::Rejected, U256::ZERO)?;
+        self.adjust_reputation(sender, deal_id, ReputationReason::DealRejected);
         
         evm::log(DealRejected { deal_id });
         
@@ -500,11 +1276,11 @@ impl Marketplace {
     
     /// Finish a deal and release funds to beneficiary
     pub fn finish_deal(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let deal = self.deals.get(U256::from(deal_id));
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
+        // Check deal exists and is still open
+        if !deal.is_open() {
             return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
@@ -513,8 +1289,8 @@ impl Marketplace {
             return Err(MarketplaceError::Unauthorized(Unauthorized {}));
         }
         
-        // Check deal is accepted
-        if !deal.accepted.get() {
+        // Check deal is accepted (streams settle through withdraw_streamed / cancel_stream)
+        if !deal.accepted.get() || deal.streaming.get() {
             return Err(MarketplaceError::InvalidState(InvalidState {}));
         }
         
@@ -523,52 +1299,122 @@ impl Marketplace {
             return Err(MarketplaceError::InvalidState(InvalidState {}));
         }
         
+        // Sub-deals settle before their parent, and not while an ancestor is disputed
+        if deal.open_children.get() != U64::ZERO || self.ancestor_disputed(deal_id) {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
         let amount = deal.amount.get();
         let beneficiary = deal.beneficiary.get();
-        let fee_percent = self.fee_percent.get();
+        let token = deal.token.get();
+        let deadline = deal.started_at.get() + U256::from(deal.duration.get()) * ONE_DAY;
         
         // Calculate fee
-        let fee = amount * U256::from(fee_percent) / U256::from(100u64);
+        let fee = amount * self.fee_percent_for(token) / U256::from(100u64);
         let payout = amount - fee;
+        self.fund_judge_pool(token, fee);
+        
+        // Update beneficiary balances (the position holder owns the claim)
+        let holder = self.claim_holder(deal_id, beneficiary);
+        self.credit_beneficiaries(deal_id, holder, token, payout);
+        self.record_volume(deal_id, token, payout);
+        
+        self.close_deal(deal_id, DealStatus::Completed, payout)?;
         
-        // Update beneficiary balance
-        let mut beneficiary_user = self.users.setter(beneficiary);
-        let current_balance = beneficiary_user.balance.get();
-        beneficiary_user.balance.set(current_balance + payout);
-        
-        // Delete deal
-        let mut deal_mut = self.deals.setter(U256::from(deal_id));
-        deal_mut.deal_id.set(U64::ZERO);
-        deal_mut.payer.set(Address::ZERO);
-        deal_mut.beneficiary.set(Address::ZERO);
-        deal_mut.amount.set(U256::ZERO);
-        deal_mut.duration.set(U64::ZERO);
-        deal_mut.started_at.set(U256::ZERO);
-        deal_mut.accepted.set(false);
-        deal_mut.disputed.set(false);
+        self.adjust_reputation(sender, deal_id, ReputationReason::DealCompleted);
+        self.adjust_team_reputation(deal_id, ReputationReason::DealCompleted);
+        if U256::from(block::timestamp()) <= deadline {
+            self.adjust_team_reputation(deal_id, ReputationReason::DeliveredOnTime);
+        }
+        self.issue_credentials(deal_id);
         
         evm::log(DealFinalized { deal_id });
         
         Ok(())
     }
     
+    /// Finish a deal and tip the beneficiary in the same call
+    /// Send the tip as value for ETH deals
+    #[payable]
+    pub fn finish_deal_with_tip(&mut self, deal_id: u64, tip_amount: U256) -> Result<(), MarketplaceError> {
+        self.finish_deal(deal_id)?;
+        
+        if tip_amount != U256::ZERO {
+            self.tip(deal_id, tip_amount)?;
+        } else if msg::value() != U256::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        Ok(())
+    }
+    
+    /// Tip the beneficiary of a completed deal (only payer)
+    /// Tips are credited directly at the tip fee rate (send them as value for ETH deals)
+    #[payable]
+    pub fn tip(&mut self, deal_id: u64, amount: U256) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (beneficiary, token) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            if deal.deal_id.get() == U64::ZERO || !deal.status().is_settled() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            if sender != deal.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            (deal.beneficiary.get(), deal.token.get())
+        };
+        
+        if amount == U256::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.deposit(sender, token, amount)?;
+        
+        let fee = amount * U256::from(self.tip_fee_percent.get()) / U256::from(100u64);
+        self.fund_judge_pool(token, fee);
+        self.credit_beneficiaries(deal_id, beneficiary, token, amount - fee);
+        
+        // Tips count towards the beneficiary's stats
+        let mut user = self.users.setter(beneficiary);
+        let tips_count = user.tips_count.get();
+        user.tips_count.set(tips_count + U64::from(1));
+        let tips_total = user.tips_total.get(token);
+        user.tips_total.setter(token).set(tips_total + amount);
+        
+        evm::log(TipSent {
+            deal_id,
+            payer: sender,
+            beneficiary,
+            token,
+            amount,
+            fee,
+        });
+        
+        Ok(())
+    }
+    
     /// Request payment after deal duration has passed
     pub fn request_deal_payment(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let deal = self.deals.get(U256::from(deal_id));
         
-        // Check deal exists
-        if deal.amount.get() == U256::ZERO {
+        // Check deal exists and is still open
+        if !deal.is_open() {
             return Err(MarketplaceError::NotFound(NotFound {}));
         }
         
-        // Only beneficiary can request payment
-        if sender != deal.beneficiary.get() {
+        // Only beneficiary or the holder of its position can request payment
+        let holder = self.claim_holder(deal_id, deal.beneficiary.get());
+        if sender != deal.beneficiary.get() && sender != holder {
             return Err(MarketplaceError::Unauthorized(Unauthorized {}));
         }
         
-        // Check deal is accepted
-        if !deal.accepted.get() {
+        // Check deal is accepted (streams settle through withdraw_streamed / cancel_stream)
+        if !deal.accepted.get() || deal.streaming.get() {
             return Err(MarketplaceError::InvalidState(InvalidState {}));
         }
         
@@ -577,6 +1423,11 @@ impl Marketplace {
             return Err(MarketplaceError::InvalidState(InvalidState {}));
         }
         
+        // Sub-deals settle before their parent, and not while an ancestor is disputed
+        if deal.open_children.get() != U64::ZERO || self.ancestor_disputed(deal_id) {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
         // Check duration + 1 week has passed
         let started_at = deal.started_at.get();
         let duration = U256::from(deal.duration.get());
@@ -588,28 +1439,24 @@ impl Marketplace {
         }
         
         let amount = deal.amount.get();
-        let beneficiary = deal.beneficiary.get();
-        let fee_percent = self.fee_percent.get();
+        let payer = deal.payer.get();
+        let token = deal.token.get();
         
-        // Calculate fee
-        let fee = amount * U256::from(fee_percent) / U256::from(100u64);
-        let payout = (amount - fee) * U256::from(10u64.pow(USDC_DECIMALS as u32));
-        
-        // Update beneficiary balance
-        let mut beneficiary_user = self.users.setter(beneficiary);
-        let current_balance = beneficiary_user.balance.get();
-        beneficiary_user.balance.set(current_balance + payout);
-        
-        // Delete deal
-        let mut deal_mut = self.deals.setter(U256::from(deal_id));
-        deal_mut.deal_id.set(U64::ZERO);
-        deal_mut.payer.set(Address::ZERO);
-        deal_mut.beneficiary.set(Address::ZERO);
-        deal_mut.amount.set(U256::ZERO);
-        deal_mut.duration.set(U64::ZERO);
-        deal_mut.started_at.set(U256::ZERO);
-        deal_mut.accepted.set(false);
-        deal_mut.disputed.set(false);
+        // Calculate fee (amount is already in token units)
+        let fee = amount * self.fee_percent_for(token) / U256::from(100u64);
+        let payout = amount - fee;
+        self.fund_judge_pool(token, fee);
+        
+        // Update beneficiary balances
+        self.credit_beneficiaries(deal_id, holder, token, payout);
+        self.record_volume(deal_id, token, payout);
+        
+        // The payer let the review window lapse
+        self.close_deal(deal_id, DealStatus::Expired, payout)?;
+        
+        self.adjust_reputation(payer, deal_id, ReputationReason::DealExpired);
+        self.adjust_team_reputation(deal_id, ReputationReason::DealCompleted);
+        self.issue_credentials(deal_id);
         
         evm::log(DealFinalized { deal_id });
         
@@ -618,7 +1465,8 @@ impl Marketplace {
     
     /// Request a dispute for a deal
     /// Create/Request a dispute for a deal
-    /// Buyer must approve marketplace to spend 50 USDC dispute fee before calling this
+    /// Buyer must approve marketplace to spend the dispute fee (see `get_dispute_fee_quote`) before calling this
+    /// The case reaches the protocol once the beneficiary matches the fee (see `match_dispute_fee`)
     pub fn create_dispute(
         &mut self,
         deal_id: u64,
@@ -632,14 +1480,18 @@ impl Marketplace {
         deal_id: u64,
         proof: String,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
         
         // Validate deal first (using immutable borrow)
         {
             let deal = self.deals.get(U256::from(deal_id));
             
-            // Check deal exists
-            if deal.amount.get() == U256::ZERO {
+            // Check deal exists and is still open
+            if !deal.is_open() {
                 return Err(MarketplaceError::NotFound(NotFound {}));
             }
             
@@ -659,166 +1511,429 @@ impl Marketplace {
             }
         }
         
+        // Dispute fees are always paid in the protocol's token, whatever the deal token
         let usdc = self.usdc_token.get();
-        let protocol_addr = self.protocol.get();
-        
-        // Transfer dispute fee (50 USDC) to protocol
-        let dispute_fee = U256::from(50u64) * U256::from(10u64.pow(USDC_DECIMALS as u32));
-        let token = IERC20::new(usdc);
-        let call = Call::new_in(self);
-        let success = token.transfer_from(call, sender, protocol_addr, dispute_fee)?;
         
-        if !success {
-            return Err(MarketplaceError::CallFailed(CallFailed {}));
-        }
+        // Hold the requester's dispute fee until the respondent matches it
+        let dispute_fee = self.get_dispute_fee_quote(deal_id)?;
+        safe_transfer_from(usdc, sender, contract::address(), dispute_fee)?;
         
-        // Mark deal as disputed
+        // Mark deal as disputed, the respondent has until the deadline to pay
         let mut deal = self.deals.setter(U256::from(deal_id));
         deal.disputed.set(true);
-        drop(deal); // Explicitly drop to release borrow
-        
-        // Call protocol to create dispute
-        let protocol = IProtocol::new(protocol_addr);
-        let call2 = Call::new_in(self);
-        protocol.create_dispute(call2, deal_id, sender, proof)?;
+        deal.status.set(U8::from(DealStatus::Disputed as u8));
+        deal.dispute_fee.set(dispute_fee);
+        deal.respondent_deadline.set(U256::from(block::timestamp()) + RESPONDENT_DEPOSIT_WINDOW);
+        deal.dispute_proof.set_str(&proof);
         
         evm::log(DisputeCreated {
             deal_id,
             requester: sender,
         });
+        evm::log(PendingDisputeFeeDeposited {
+            deal_id,
+            party: sender,
+            amount: dispute_fee,
+        });
         
         Ok(())
     }
     
-    /// Add evidence for a dispute (payer side)
-    pub fn add_dispute_evidence_for_payer(
+    /// Request a dispute, approving the dispute fee with an EIP-2612 signature
+    pub fn request_dispute_with_permit(
         &mut self,
-        dispute_id: u64,
+        deal_id: u64,
         proof: String,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
     ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
-        let dispute = self.disputes.get(U64::from(dispute_id));
-        
-        // Check dispute exists
-        if dispute.deal_id.get() == U64::ZERO {
-            return Err(MarketplaceError::NotFound(NotFound {}));
-        }
+        let usdc = self.usdc_token.get();
+        let dispute_fee = self.get_dispute_fee_quote(deal_id)?;
+        self.try_permit(usdc, msg_sender(), dispute_fee, deadline, v, r, s);
+        self.request_dispute(deal_id, proof)
+    }
+    
+    /// Match the requester's dispute fee before the deadline (only the deal's beneficiary)
+    /// The case and the requester's fee then go to the protocol. This deposit is held until
+    /// the ruling: refunded if the beneficiary wins, paid back to the requester otherwise
+    /// Beneficiary must approve marketplace to spend the deal's dispute fee before calling this
+    /// Returns the protocol dispute id
+    pub fn match_dispute_fee(&mut self, deal_id: u64) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
         
-        // Only requester can add evidence
-        if sender != dispute.requester.get() {
-            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
-        }
+        let (fee, requester, proof) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            // Only a requested dispute still waiting for the respondent
+            if deal.status() != DealStatus::Disputed || deal.dispute_id.get() != U64::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != deal.beneficiary.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Past the deadline the requester wins by default
+            if U256::from(block::timestamp()) > deal.respondent_deadline.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (deal.dispute_fee.get(), deal.payer.get(), deal.dispute_proof.get_string())
+        };
         
-        // Proof cannot be empty
-        if proof.is_empty() {
-            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
-        }
+        let usdc = self.usdc_token.get();
+        safe_transfer_from(usdc, sender, contract::address(), fee)?;
         
-        // Call protocol to update dispute
+        // Both sides paid: the requester's fee pays the protocol's judges
         let protocol_addr = self.protocol.get();
+        safe_transfer(usdc, protocol_addr, fee)?;
+        
+        // Call protocol to create dispute
         let protocol = IProtocol::new(protocol_addr);
         let call = Call::new_in(self);
-        protocol.update_dispute_for_payer(call, dispute_id, sender, proof)?;
-        
-        Ok(())
-    }
-    
-    /// Add evidence for a dispute (beneficiary side)
-    pub fn add_dispute_evidence_for_beneficiary(
-        &mut self,
-        dispute_id: u64,
-        proof: String,
-    ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
-        let dispute = self.disputes.get(U64::from(dispute_id));
-        
-        // Check dispute exists
-        if dispute.deal_id.get() == U64::ZERO {
-            return Err(MarketplaceError::NotFound(NotFound {}));
-        }
-        
-        // Get deal to verify beneficiary
-        let deal_id = dispute.deal_id.get();
-        let deal = self.deals.get(U256::from(deal_id));
+        let dispute_id = protocol.create_dispute(call, deal_id, requester, proof, fee)?;
         
-        // Only beneficiary can add evidence
-        if sender != deal.beneficiary.get() {
-            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
-        }
+        let mut dispute = self.disputes.setter(U64::from(dispute_id));
+        dispute.deal_id.set(U64::from(deal_id));
+        dispute.requester.set(requester);
+        dispute.is_open.set(true);
+        dispute.waiting_for_judges.set(true);
+        dispute.respondent.set(sender);
+        dispute.fee.set(fee);
+        dispute.respondent_paid.set(true);
         
-        // Proof cannot be empty
-        if proof.is_empty() {
-            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
-        }
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        deal.dispute_id.set(U64::from(dispute_id));
+        deal.dispute_proof.erase();
         
-        // Call protocol to update dispute
-        let protocol_addr = self.protocol.get();
-        let protocol = IProtocol::new(protocol_addr);
-        let call = Call::new_in(self);
-        protocol.update_dispute_for_beneficiary(call, dispute_id, sender, proof)?;
+        evm::log(DisputeRequested {
+            dispute_id: U256::from(dispute_id),
+            requester,
+        });
+        evm::log(DisputeFeeDeposited {
+            dispute_id,
+            party: requester,
+            amount: fee,
+        });
+        evm::log(DisputeFeeDeposited {
+            dispute_id,
+            party: sender,
+            amount: fee,
+        });
         
-        Ok(())
+        Ok(dispute_id)
     }
     
-    /// Apply the result of a resolved dispute
-    pub fn apply_dispute_result(
+    /// Match the requester's dispute fee, approving it with an EIP-2612 signature
+    pub fn match_dispute_fee_with_permit(
         &mut self,
-        dispute_id: u64,
         deal_id: u64,
-    ) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> Result<u64, MarketplaceError> {
+        let usdc = self.usdc_token.get();
+        let fee = self.deals.get(U256::from(deal_id)).dispute_fee.get();
+        self.try_permit(usdc, msg_sender(), fee, deadline, v, r, s);
+        self.match_dispute_fee(deal_id)
+    }
+    
+    /// Deposit the respondent's dispute fee for a dispute that reached the protocol
+    /// before its respondent paid (only the deal's beneficiary). Disputes requested
+    /// since respondent deadlines were introduced go through `match_dispute_fee`.
+    /// The deposit is held until the ruling: refunded if the beneficiary wins,
+    /// paid back to the requester in place of their fee otherwise
+    pub fn deposit_dispute_fee(&mut self, dispute_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
         
-        // Validate and get values (using immutable borrows)
-        let (amount, fee_percent, requester, beneficiary) = {
+        let fee = {
             let dispute = self.disputes.get(U64::from(dispute_id));
             
-            // Check dispute exists
-            if dispute.deal_id.get() == U64::ZERO {
+            // Check dispute exists and is still open
+            if dispute.deal_id.get() == U64::ZERO || !dispute.is_open.get() {
                 return Err(MarketplaceError::NotFound(NotFound {}));
             }
             
-            let deal = self.deals.get(U256::from(deal_id));
-            
-            // Only involved parties can execute
-            if sender != dispute.requester.get() && sender != deal.beneficiary.get() {
+            if sender != dispute.respondent.get() {
                 return Err(MarketplaceError::Unauthorized(Unauthorized {}));
             }
             
-            (deal.amount.get(), self.fee_percent.get(), dispute.requester.get(), deal.beneficiary.get())
+            if dispute.respondent_paid.get() {
+                return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+            }
+            
+            dispute.fee.get()
         };
         
-        // Get dispute result from protocol
-        let protocol_addr = self.protocol.get();
-        let protocol = IProtocol::new(protocol_addr);
-        let call = Call::new_in(self);
-        let winner = protocol.execute_dispute_result(call, dispute_id)?;
+        let usdc = self.usdc_token.get();
+        safe_transfer_from(usdc, sender, contract::address(), fee)?;
         
-        // Calculate payout
-        let fee = amount * U256::from(fee_percent) / U256::from(100u64);
-        let payout = amount - fee;
+        self.disputes.setter(U64::from(dispute_id)).respondent_paid.set(true);
         
-        // Determine winner address
+        evm::log(DisputeFeeDeposited {
+            dispute_id,
+            party: sender,
+            amount: fee,
+        });
+        
+        Ok(())
+    }
+    
+    /// Deposit the respondent's dispute fee, approving it with an EIP-2612 signature
+    pub fn deposit_dispute_fee_with_permit(
+        &mut self,
+        dispute_id: u64,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> Result<(), MarketplaceError> {
+        let usdc = self.usdc_token.get();
+        let fee = self.disputes.get(U64::from(dispute_id)).fee.get();
+        self.try_permit(usdc, msg_sender(), fee, deadline, v, r, s);
+        self.deposit_dispute_fee(dispute_id)
+    }
+    
+    /// Rule for the requester when the beneficiary did not match the dispute fee in time (only payer)
+    /// Settles like a ruling the requester won, and refunds the requester's fee
+    pub fn claim_default_judgment(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (amount, token, dispute_fee) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            // Only a requested dispute still waiting for the respondent
+            if deal.status() != DealStatus::Disputed || deal.dispute_id.get() != U64::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != deal.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            if U256::from(block::timestamp()) <= deal.respondent_deadline.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Sub-deal funds stay locked while a parent deal is disputed
+            if self.ancestor_disputed(deal_id) {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Streamed payouts are final, only the rest is at stake
+            (deal.amount.get() - deal.streamed.get(), deal.token.get(), deal.dispute_fee.get())
+        };
+        
+        // Calculate payout
+        let fee = amount * self.fee_percent_for(token) / U256::from(100u64);
+        let payout = amount - fee;
+        self.fund_judge_pool(token, fee);
+        
+        self.credit(sender, token, payout);
+        self.close_deal(deal_id, DealStatus::Resolved, U256::ZERO)?;
+        
+        self.adjust_reputation(sender, deal_id, ReputationReason::DisputeWon);
+        self.adjust_team_reputation(deal_id, ReputationReason::DisputeLost);
+        
+        // The requester's fee never left the marketplace
+        let usdc = self.usdc_token.get();
+        self.credit(sender, usdc, dispute_fee);
+        self.deals.setter(U256::from(deal_id)).dispute_proof.erase();
+        
+        evm::log(PendingDisputeFeeRefunded {
+            deal_id,
+            party: sender,
+            amount: dispute_fee,
+        });
+        evm::log(DefaultJudgment {
+            deal_id,
+            winner: sender,
+        });
+        
+        Ok(())
+    }
+    
+    /// Add evidence for a dispute (payer side)
+    pub fn add_dispute_evidence_for_payer(
+        &mut self,
+        dispute_id: u64,
+        proof: String,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let dispute = self.disputes.get(U64::from(dispute_id));
+        
+        // Check dispute exists
+        if dispute.deal_id.get() == U64::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        // Only requester can add evidence
+        if sender != dispute.requester.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        // Proof cannot be empty
+        if proof.is_empty() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        // Call protocol to update dispute
+        let protocol_addr = self.protocol.get();
+        let protocol = IProtocol::new(protocol_addr);
+        let call = Call::new_in(self);
+        protocol.update_dispute_for_payer(call, dispute_id, sender, proof)?;
+        
+        Ok(())
+    }
+    
+    /// Add evidence for a dispute (beneficiary side)
+    pub fn add_dispute_evidence_for_beneficiary(
+        &mut self,
+        dispute_id: u64,
+        proof: String,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let dispute = self.disputes.get(U64::from(dispute_id));
+        
+        // Check dispute exists
+        if dispute.deal_id.get() == U64::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        // Get deal to verify beneficiary
+        let deal_id = dispute.deal_id.get();
+        let deal = self.deals.get(U256::from(deal_id));
+        
+        // Only beneficiary can add evidence
+        if sender != deal.beneficiary.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        // Proof cannot be empty
+        if proof.is_empty() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        // Call protocol to update dispute
+        let protocol_addr = self.protocol.get();
+        let protocol = IProtocol::new(protocol_addr);
+        let call = Call::new_in(self);
+        protocol.update_dispute_for_beneficiary(call, dispute_id, sender, proof)?;
+        
+        Ok(())
+    }
+    
+    /// Apply the result of a resolved dispute
+    pub fn apply_dispute_result(
+        &mut self,
+        dispute_id: u64,
+        deal_id: u64,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        // Validate and get values (using immutable borrows)
+        let (amount, token, requester, beneficiary, dispute_fee, respondent_paid) = {
+            let dispute = self.disputes.get(U64::from(dispute_id));
+            
+            // Check dispute exists
+            if dispute.deal_id.get() == U64::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            // The ruling only settles the deal the dispute was opened over
+            let deal_dispute_id = u64::from_le_bytes(deal.dispute_id.get().to_le_bytes());
+            let dispute_deal_id = u64::from_le_bytes(dispute.deal_id.get().to_le_bytes());
+            if !dispute_matches_deal(dispute_id, deal_id, deal_dispute_id, dispute_deal_id) {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Only involved parties can execute
+            if sender != dispute.requester.get() && sender != deal.beneficiary.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Each dispute settles its deal once
+            if deal.status() != DealStatus::Disputed {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Sub-deal funds stay locked while a parent deal is disputed
+            if self.ancestor_disputed(deal_id) {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Streamed payouts are final, only the rest is at stake
+            let remaining = deal.amount.get() - deal.streamed.get();
+            (
+                remaining,
+                deal.token.get(),
+                dispute.requester.get(),
+                deal.beneficiary.get(),
+                dispute.fee.get(),
+                dispute.respondent_paid.get(),
+            )
+        };
+        
+        // Get dispute result from protocol
+        let protocol_addr = self.protocol.get();
+        let protocol = IProtocol::new(protocol_addr);
+        let call = Call::new_in(self);
+        let winner = protocol.execute_dispute_result(call, dispute_id)?;
+        
+        // Calculate payout
+        let fee = amount * self.fee_percent_for(token) / U256::from(100u64);
+        let payout = amount - fee;
+        self.fund_judge_pool(token, fee);
+        
+        // Determine winner address
         let winner_address = if winner {
             requester
         } else {
             beneficiary
         };
         
-        // Update winner balance
-        let mut winner_user = self.users.setter(winner_address);
-        let current_balance = winner_user.balance.get();
-        winner_user.balance.set(current_balance + payout);
-        
-        // Delete deal
-        let mut deal_mut = self.deals.setter(U256::from(deal_id));
-        deal_mut.deal_id.set(U64::ZERO);
-        deal_mut.payer.set(Address::ZERO);
-        deal_mut.beneficiary.set(Address::ZERO);
-        deal_mut.amount.set(U256::ZERO);
-        deal_mut.duration.set(U64::ZERO);
-        deal_mut.started_at.set(U256::ZERO);
-        deal_mut.accepted.set(false);
-        deal_mut.disputed.set(false);
+        // Update winner balance (split across members when the team wins)
+        let beneficiary_payout = if winner {
+            self.credit(winner_address, token, payout);
+            U256::ZERO
+        } else {
+            let holder = self.claim_holder(deal_id, beneficiary);
+            self.credit_beneficiaries(deal_id, holder, token, payout);
+            self.record_volume(deal_id, token, payout);
+            payout
+        };
+        
+        self.close_deal(deal_id, DealStatus::Resolved, beneficiary_payout)?;
+        
+        let (payer_reason, team_reason) = if winner {
+            (ReputationReason::DisputeWon, ReputationReason::DisputeLost)
+        } else {
+            (ReputationReason::DisputeLost, ReputationReason::DisputeWon)
+        };
+        self.adjust_reputation(requester, deal_id, payer_reason);
+        self.adjust_team_reputation(deal_id, team_reason);
+        
+        // The team won the dispute, so the work counts as delivered
+        if !winner {
+            self.issue_credentials(deal_id);
+        }
+        
+        // Loser pays: the held respondent deposit refunds whichever side won
+        if respondent_paid {
+            let refunded = if winner { requester } else { beneficiary };
+            let usdc = self.usdc_token.get();
+            self.credit(refunded, usdc, dispute_fee);
+            evm::log(DisputeFeeRefunded {
+                dispute_id,
+                party: refunded,
+                amount: dispute_fee,
+            });
+        }
         
         // Delete dispute
         let mut dispute_mut = self.disputes.setter(U64::from(dispute_id));
@@ -835,17 +1950,20 @@ impl Marketplace {
         Ok(())
     }
     
-    /// Withdraw user balance
+    /// Withdraw user balance in the default token
     pub fn withdraw(&mut self) -> Result<(), MarketplaceError> {
-        let sender = msg::sender();
+        let token = self.usdc_token.get();
+        self.withdraw_token(token)
+    }
+    
+    /// Withdraw user balance in a given token (NATIVE_TOKEN, 0xEeee...EEeE, for ETH)
+    /// Open to any address with a balance, such as unregistered position holders
+    pub fn withdraw_token(&mut self, token: Address) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        self.settle_legacy_balance(sender);
         let mut user = self.users.setter(sender);
         
-        // Check user is registered
-        if user.user_address.get() != sender {
-            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
-        }
-        
-        let balance = user.balance.get();
+        let balance = user.balances.get(token);
         
         // Check sufficient balance
         if balance == U256::ZERO {
@@ -853,20 +1971,20 @@ impl Marketplace {
         }
         
         // Reset balance before transfer (reentrancy protection)
-        user.balance.set(U256::ZERO);
+        user.balances.setter(token).set(U256::ZERO);
         
-        // Transfer USDC to user
-        let usdc = self.usdc_token.get();
-        let token = IERC20::new(usdc);
-        let call = Call::new_in(self);
-        let success = token.transfer(call, sender, balance)?;
-        
-        if !success {
-            return Err(MarketplaceError::CallFailed(CallFailed {}));
+        if token == NATIVE_TOKEN {
+            // Balance was zeroed above, so a reentrant call finds nothing to withdraw
+            call::transfer_eth(sender, balance)
+                .map_err(|_| MarketplaceError::CallFailed(CallFailed {}))?;
+        } else {
+            // Transfer tokens to user
+            safe_transfer(token, sender, balance)?;
         }
         
         evm::log(UserWithdrew {
             user: sender,
+            token,
             amount: balance,
         });
         
@@ -874,56 +1992,2154 @@ impl Marketplace {
     }
     
     // ====================================
-    //        VIEW FUNCTIONS          
+    //             JOB BOARD
     // ====================================
     
-    /// Get protocol address
-    pub fn protocol_address(&self) -> Address {
-        self.protocol.get()
-    }
-    
-    /// Get deal ID counter
-    pub fn deal_id_counter(&self) -> u64 {
-        u64::from_le_bytes(self.deal_id_counter.get().to_le_bytes())
+    /// Post an open job that registered beneficiaries can bid on
+    /// Nothing is escrowed until the payer selects a bid
+    pub fn post_job(
+        &mut self,
+        token: Address,
+        budget: U256,
+        duration: u64,
+        terms_hash: FixedBytes<32>,
+        deadline: U256,
+    ) -> Result<u64, MarketplaceError> {
+        self.open_job(msg_sender(), token, budget, duration, terms_hash, deadline)
     }
     
-    /// Get user info
-    pub fn get_user(&self, user_address: Address) -> (Address, U256, i8, i8, bool, bool, bool) {
-        let user = self.users.get(user_address);
-        (
-            user.user_address.get(),
-            user.balance.get(),
-            i8::from_le_bytes(user.reputation_as_user.get().to_le_bytes()),
-            i8::from_le_bytes(user.reputation_as_judge.get().to_le_bytes()),
-            user.is_payer.get(),
-            user.is_beneficiary.get(),
-            user.is_judge.get(),
-        )
+    /// Post a sealed-bid reverse auction
+    /// The budget is escrowed up front (send it as value for ETH jobs) so the winner
+    /// can be settled by anyone once the reveal window closes. Bids are committed
+    /// until `deadline` and revealed until `reveal_deadline`.
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn post_sealed_job(
+        &mut self,
+        token: Address,
+        budget: U256,
+        duration: u64,
+        terms_hash: FixedBytes<32>,
+        deadline: U256,
+        reveal_deadline: U256,
+        bid_bond: U256,
+        reputation_weight: u8,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if reveal_deadline <= deadline || reputation_weight > 100 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let job_id = self.open_job(sender, token, budget, duration, terms_hash, deadline)?;
+        
+        let mut job = self.jobs.setter(U64::from(job_id));
+        job.sealed.set(true);
+        job.reveal_deadline.set(reveal_deadline);
+        job.bid_bond.set(bid_bond);
+        job.reputation_weight.set(U8::from(reputation_weight));
+        
+        self.deposit(sender, token, budget)?;
+        
+        evm::log(SealedJobPosted {
+            job_id,
+            reveal_deadline,
+            bid_bond,
+            reputation_weight,
+        });
+        
+        Ok(job_id)
     }
     
-    /// Get deal info
-    pub fn get_deal(&self, deal_id: u64) -> (u64, Address, Address, U256, U256, u64, bool, bool) {
-        let deal = self.deals.get(U256::from(deal_id));
-        (
-            u64::from_le_bytes(deal.deal_id.get().to_le_bytes()),
-            deal.payer.get(),
-            deal.beneficiary.get(),
-            deal.amount.get(),
-            deal.started_at.get(),
-            u64::from_le_bytes(deal.duration.get().to_le_bytes()),
-            deal.accepted.get(),
-            deal.disputed.get(),
-        )
+    /// Cancel an open job (only the payer, before a bid is selected)
+    /// Sealed auctions can only be cancelled before the first commit, refunding the budget
+    pub fn cancel_job(&mut self, job_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let mut job = self.jobs.setter(U64::from(job_id));
+        
+        // Check job exists
+        if job.payer.get() == Address::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        if sender != job.payer.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        if !job.open.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Committed bidders have bonds locked in the auction
+        let sealed = job.sealed.get();
+        if sealed && job.bids_count.get() != U64::ZERO {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        job.open.set(false);
+        let token = job.token.get();
+        let budget = job.budget.get();
+        
+        if sealed {
+            self.credit(sender, token, budget);
+        }
+        
+        evm::log(JobCancelled { job_id });
+        
+        Ok(())
     }
     
-    /// Get dispute info
-    pub fn get_dispute(&self, dispute_id: u64) -> (u64, Address, bool, bool) {
-        let dispute = self.disputes.get(U64::from(dispute_id));
-        (
-            u64::from_le_bytes(dispute.deal_id.get().to_le_bytes()),
-            dispute.requester.get(),
-            dispute.is_open.get(),
-            dispute.waiting_for_judges.get(),
-        )
+    /// Bid on an open job (only registered beneficiaries, one bid per bidder)
+    pub fn submit_bid(
+        &mut self,
+        job_id: u64,
+        price: U256,
+        duration: u64,
+        proposal_hash: FixedBytes<32>,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        // Only beneficiaries (sellers) can bid
+        if !self.users.get(sender).is_beneficiary.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let mut job = self.jobs.setter(U64::from(job_id));
+        
+        // Check job exists
+        if job.payer.get() == Address::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        // Payer cannot bid on their own job
+        if sender == job.payer.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        // Check bidding is still open (sealed auctions use commit_bid)
+        if job.sealed.get() || !job.open.get() || U256::from(block::timestamp()) > job.deadline.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Price must be within the job budget
+        if price == U256::ZERO || price > job.budget.get() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        if job.bid_of.get(sender) != U64::ZERO {
+            return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+        }
+        
+        let bid_index = job.bids_count.get();
+        let mut bid = job.bids.setter(bid_index);
+        bid.bidder.set(sender);
+        bid.price.set(price);
+        bid.duration.set(U64::from(duration));
+        bid.proposal_hash.set(proposal_hash);
+        
+        job.bids_count.set(bid_index + U64::from(1));
+        job.bid_of.setter(sender).set(bid_index + U64::from(1));
+        
+        let bid_index_u64 = u64::from_le_bytes(bid_index.to_le_bytes());
+        evm::log(BidSubmitted {
+            job_id,
+            bid_index: bid_index_u64,
+            bidder: sender,
+            price,
+        });
+        
+        Ok(bid_index_u64)
+    }
+    
+    /// Withdraw a bid that has not been selected
+    pub fn withdraw_bid(&mut self, job_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let mut job = self.jobs.setter(U64::from(job_id));
+        
+        let bid_position = job.bid_of.get(sender);
+        if bid_position == U64::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        // Bids are binding once the job is awarded, sealed bids until settlement
+        if job.sealed.get() || !job.open.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let bid_index = bid_position - U64::from(1);
+        let mut bid = job.bids.setter(bid_index);
+        if bid.withdrawn.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        bid.withdrawn.set(true);
+        
+        evm::log(BidWithdrawn {
+            job_id,
+            bid_index: u64::from_le_bytes(bid_index.to_le_bytes()),
+        });
+        
+        Ok(())
+    }
+    
+    /// Select the winning bid (only the payer)
+    /// The bid price is escrowed (send it as value for ETH jobs) and the
+    /// resulting deal starts immediately as accepted
+    #[payable]
+    pub fn select_bid(&mut self, job_id: u64, bid_index: u64) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (token, bidder, price, duration) = {
+            let job = self.jobs.get(U64::from(job_id));
+            
+            // Check job exists
+            if job.payer.get() == Address::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != job.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Sealed auctions pick their winner in settle_auction
+            if job.sealed.get() || !job.open.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            if U64::from(bid_index) >= job.bids_count.get() {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            let bid = job.bids.get(U64::from(bid_index));
+            if bid.withdrawn.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (
+                job.token.get(),
+                bid.bidder.get(),
+                bid.price.get(),
+                u64::from_le_bytes(bid.duration.get().to_le_bytes()),
+            )
+        };
+        
+        // Same checks as a direct deal (pause, allowlist, registered roles)
+        self.validate_new_deal(sender, bidder, token, price)?;
+        
+        // Close the job before moving funds
+        self.jobs.setter(U64::from(job_id)).open.set(false);
+        
+        self.deposit(sender, token, price)?;
+        
+        let deal_id = self.record_deal(sender, bidder, token, price, duration);
+        self.start_deal(deal_id);
+        
+        let mut job = self.jobs.setter(U64::from(job_id));
+        job.awarded.set(true);
+        job.deal_id.set(U64::from(deal_id));
+        
+        evm::log(JobAwarded {
+            job_id,
+            bid_index,
+            deal_id,
+        });
+        
+        Ok(deal_id)
+    }
+    
+    // ====================================
+    //        SEALED-BID AUCTIONS
+    // ====================================
+    
+    /// Commit a sealed bid: `commitment` is
+    /// keccak256(abi.encodePacked(uint64 job_id, address bidder, uint256 price, bytes secret)),
+    /// binding it to the job and bidder so it can't be copied by another bidder
+    /// The job's bid bond is locked (send it as value for ETH jobs) and returned on reveal
+    #[payable]
+    pub fn commit_bid(
+        &mut self,
+        job_id: u64,
+        duration: u64,
+        proposal_hash: FixedBytes<32>,
+        commitment: FixedBytes<32>,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        // Only beneficiaries (sellers) can bid
+        if !self.users.get(sender).is_beneficiary.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let (token, bid_bond, bid_index) = {
+            let job = self.jobs.get(U64::from(job_id));
+            
+            // Check job exists
+            if job.payer.get() == Address::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            // Payer cannot bid on their own job
+            if sender == job.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Check the commit phase is running
+            if !job.sealed.get() || !job.open.get() || U256::from(block::timestamp()) > job.deadline.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            if job.bid_of.get(sender) != U64::ZERO {
+                return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+            }
+            
+            if job.bids_count.get() >= U64::from(MAX_SEALED_BIDS) {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (job.token.get(), job.bid_bond.get(), job.bids_count.get())
+        };
+        
+        let mut job = self.jobs.setter(U64::from(job_id));
+        let mut bid = job.bids.setter(bid_index);
+        bid.bidder.set(sender);
+        bid.duration.set(U64::from(duration));
+        bid.proposal_hash.set(proposal_hash);
+        bid.commitment.set(commitment);
+        
+        job.bids_count.set(bid_index + U64::from(1));
+        job.bid_of.setter(sender).set(bid_index + U64::from(1));
+        
+        self.deposit(sender, token, bid_bond)?;
+        
+        let bid_index_u64 = u64::from_le_bytes(bid_index.to_le_bytes());
+        evm::log(BidCommitted {
+            job_id,
+            bid_index: bid_index_u64,
+            bidder: sender,
+        });
+        
+        Ok(bid_index_u64)
+    }
+    
+    /// Reveal a sealed bid during the reveal window, refunding its bond
+    /// The commitment must have been computed for this job, the caller and the revealed price
+    pub fn reveal_bid(
+        &mut self,
+        job_id: u64,
+        price: U256,
+        secret: Vec<u8>,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let mut job = self.jobs.setter(U64::from(job_id));
+        
+        let bid_position = job.bid_of.get(sender);
+        if !job.sealed.get() || bid_position == U64::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        // Check the reveal phase is running
+        let now = U256::from(block::timestamp());
+        if !job.open.get() || now <= job.deadline.get() || now > job.reveal_deadline.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Price must be within the job budget
+        if price == U256::ZERO || price > job.budget.get() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let token = job.token.get();
+        let bid_bond = job.bid_bond.get();
+        let bid_index = bid_position - U64::from(1);
+        let mut bid = job.bids.setter(bid_index);
+        
+        if bid.revealed.get() {
+            return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+        }
+        
+        if bid_commitment(job_id, sender, price, &secret) != bid.commitment.get() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        bid.revealed.set(true);
+        bid.price.set(price);
+        
+        self.credit(sender, token, bid_bond);
+        
+        evm::log(BidRevealed {
+            job_id,
+            bid_index: u64::from_le_bytes(bid_index.to_le_bytes()),
+            price,
+        });
+        
+        Ok(())
+    }
+    
+    /// Settle a sealed auction once the reveal window has closed (anyone can call)
+    /// The best revealed bid wins: lowest price, or lowest reputation-weighted price
+    /// when the job has a reputation weight. The winning price is escrowed from the
+    /// budget into an accepted deal, the rest of the budget and any forfeited
+    /// bonds are credited to the payer. Returns the deal ID, or 0 if no bid could win.
+    pub fn settle_auction(&mut self, job_id: u64) -> Result<u64, MarketplaceError> {
+        let (payer, token, budget, bid_bond, reputation_weight, bids_count) = {
+            let job = self.jobs.get(U64::from(job_id));
+            
+            if !job.sealed.get() {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if !job.open.get() || U256::from(block::timestamp()) <= job.reveal_deadline.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (
+                job.payer.get(),
+                job.token.get(),
+                job.budget.get(),
+                job.bid_bond.get(),
+                u8::from_le_bytes(job.reputation_weight.get().to_le_bytes()),
+                u64::from_le_bytes(job.bids_count.get().to_le_bytes()),
+            )
+        };
+        
+        // Pick the winner and collect bonds of unrevealed bids
+        let mut winner: Option<(u64, Address, U256, u64)> = None;
+        let mut best_score = U256::MAX;
+        let mut forfeited = U256::ZERO;
+        
+        for i in 0..bids_count {
+            let job = self.jobs.get(U64::from(job_id));
+            let bid = job.bids.get(U64::from(i));
+            
+            if !bid.revealed.get() {
+                forfeited += bid_bond;
+                evm::log(BidBondForfeited {
+                    job_id,
+                    bid_index: i,
+                    amount: bid_bond,
+                });
+                continue;
+            }
+            
+            // Bidders must still be registered beneficiaries
+            let bidder = bid.bidder.get();
+            let bidder_user = self.users.get(bidder);
+            if !bidder_user.is_beneficiary.get() {
+                continue;
+            }
+            
+            let reputation = bidder_user.score_as_user.current() / reputation::SCALE;
+            let price = bid.price.get();
+            let score = bid_score(price, reputation, reputation_weight);
+            if score < best_score {
+                best_score = score;
+                winner = Some((i, bidder, price, u64::from_le_bytes(bid.duration.get().to_le_bytes())));
+            }
+        }
+        
+        self.jobs.setter(U64::from(job_id)).open.set(false);
+        
+        let Some((bid_index, bidder, price, duration)) = winner else {
+            // No valid bid: the whole budget goes back to the payer
+            self.credit(payer, token, budget + forfeited);
+            evm::log(JobCancelled { job_id });
+            return Ok(0);
+        };
+        
+        // Budget was escrowed at posting, only the winning price stays in the deal
+        self.credit(payer, token, budget - price + forfeited);
+        
+        let deal_id = self.record_deal(payer, bidder, token, price, duration);
+        self.start_deal(deal_id);
+        
+        let mut job = self.jobs.setter(U64::from(job_id));
+        job.awarded.set(true);
+        job.deal_id.set(U64::from(deal_id));
+        
+        evm::log(JobAwarded {
+            job_id,
+            bid_index,
+            deal_id,
+        });
+        
+        Ok(deal_id)
+    }
+    
+    // ====================================
+    //             RETAINERS
+    // ====================================
+    
+    /// Create a recurring retainer pre-funding `periods` payments of `amount_per_period`
+    /// The total is escrowed now (send it as value for ETH retainers). Once stopped,
+    /// the current period and `notice_periods` more are still paid.
+    #[payable]
+    pub fn create_retainer(
+        &mut self,
+        beneficiary: Address,
+        token: Address,
+        amount_per_period: U256,
+        period_days: u64,
+        periods: u64,
+        notice_periods: u64,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if period_days == 0 || periods == 0 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.validate_new_deal(sender, beneficiary, token, amount_per_period)?;
+        
+        // Check the whole escrow fits in a uint256
+        let Some(total) = amount_per_period.checked_mul(U256::from(periods)) else {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        };
+        self.deposit(sender, token, total)?;
+        
+        let retainer_id = self.retainer_id_counter.get();
+        let retainer_id_u64 = u64::from_le_bytes(retainer_id.to_le_bytes());
+        
+        let mut retainer = self.retainers.setter(retainer_id);
+        retainer.payer.set(sender);
+        retainer.beneficiary.set(beneficiary);
+        retainer.token.set(token);
+        retainer.amount_per_period.set(amount_per_period);
+        retainer.period_days.set(U64::from(period_days));
+        retainer.periods.set(U64::from(periods));
+        retainer.notice_periods.set(U64::from(notice_periods));
+        
+        self.retainer_id_counter.set(retainer_id + U64::from(1));
+        
+        evm::log(RetainerCreated {
+            retainer_id: retainer_id_u64,
+            payer: sender,
+            beneficiary,
+            token,
+            amount_per_period,
+            periods,
+        });
+        
+        Ok(retainer_id_u64)
+    }
+    
+    /// Accept a retainer (only beneficiary), starting the first period
+    pub fn accept_retainer(&mut self, retainer_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        let mut retainer = self.retainers.setter(U64::from(retainer_id));
+        
+        // Check retainer exists
+        if retainer.payer.get() == Address::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        if sender != retainer.beneficiary.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        if retainer.accepted.get() {
+            return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+        }
+        
+        if retainer.stopped.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        retainer.accepted.set(true);
+        retainer.started_at.set(U256::from(block::timestamp()));
+        
+        evm::log(RetainerAccepted { retainer_id });
+        
+        Ok(())
+    }
+    
+    /// Claim every elapsed period that has not been claimed or moved to a dispute
+    pub fn claim_retainer(&mut self, retainer_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        let retainer = self.retainers.get(U64::from(retainer_id));
+        
+        // Check retainer exists
+        if retainer.payer.get() == Address::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        if sender != retainer.beneficiary.get() {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        if !retainer.accepted.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let claimed = u64::from_le_bytes(retainer.claimed_periods.get().to_le_bytes());
+        let elapsed = self.retainer_elapsed_periods(retainer_id);
+        if elapsed <= claimed {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Disputed periods are settled through their own deal
+        let mut payable_periods = 0u64;
+        for period in claimed..elapsed {
+            if retainer.period_deals.get(U64::from(period)) == U64::ZERO {
+                payable_periods += 1;
+            }
+        }
+        
+        let token = retainer.token.get();
+        let Some(amount) = retainer.amount_per_period.get().checked_mul(U256::from(payable_periods)) else {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        };
+        
+        // Calculate fee
+        let fee = amount * self.fee_percent_for(token) / U256::from(100u64);
+        let payout = amount - fee;
+        self.fund_judge_pool(token, fee);
+        
+        self.retainers.setter(U64::from(retainer_id)).claimed_periods.set(U64::from(elapsed));
+        self.credit(sender, token, payout);
+        
+        evm::log(RetainerClaimed {
+            retainer_id,
+            periods: payable_periods,
+            payout,
+        });
+        
+        Ok(())
+    }
+    
+    /// Stop a retainer (only payer)
+    /// Before acceptance everything is refunded, afterwards the current period and
+    /// the notice periods remain payable and the rest is refunded
+    pub fn stop_retainer(&mut self, retainer_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (payer, token, amount_per_period, periods, end_period) = {
+            let retainer = self.retainers.get(U64::from(retainer_id));
+            
+            // Check retainer exists
+            if retainer.payer.get() == Address::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != retainer.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            if retainer.stopped.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            let periods = u64::from_le_bytes(retainer.periods.get().to_le_bytes());
+            let end_period = if retainer.accepted.get() {
+                let notice = u64::from_le_bytes(retainer.notice_periods.get().to_le_bytes());
+                let current = self.retainer_current_period(retainer_id);
+                periods.min(current.saturating_add(1).saturating_add(notice))
+            } else {
+                0
+            };
+            
+            (
+                retainer.payer.get(),
+                retainer.token.get(),
+                retainer.amount_per_period.get(),
+                periods,
+                end_period,
+            )
+        };
+        
+        let Some(refund) = amount_per_period.checked_mul(U256::from(periods - end_period)) else {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        };
+        
+        let mut retainer = self.retainers.setter(U64::from(retainer_id));
+        retainer.stopped.set(true);
+        retainer.periods.set(U64::from(end_period));
+        
+        self.credit(payer, token, refund);
+        
+        evm::log(RetainerStopped {
+            retainer_id,
+            end_period,
+            refund,
+        });
+        
+        Ok(())
+    }
+    
+    /// Dispute the current retainer period (only payer)
+    /// The period's payment moves into its own accepted deal that goes through the
+    /// regular dispute flow, so the other periods keep paying out. The dispute
+    /// fee must be approved as for `request_dispute`.
+    pub fn dispute_retainer_period(
+        &mut self,
+        retainer_id: u64,
+        proof: String,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (beneficiary, token, amount, period_days, period) = {
+            let retainer = self.retainers.get(U64::from(retainer_id));
+            
+            // Check retainer exists
+            if retainer.payer.get() == Address::ZERO {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != retainer.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            if !retainer.accepted.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Only the running, unclaimed period can be disputed
+            let period = self.retainer_current_period(retainer_id);
+            if U64::from(period) >= retainer.periods.get() || U64::from(period) < retainer.claimed_periods.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            if retainer.period_deals.get(U64::from(period)) != U64::ZERO {
+                return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+            }
+            
+            (
+                retainer.beneficiary.get(),
+                retainer.token.get(),
+                retainer.amount_per_period.get(),
+                u64::from_le_bytes(retainer.period_days.get().to_le_bytes()),
+                period,
+            )
+        };
+        
+        // Funds are already escrowed by the retainer
+        let deal_id = self.record_deal(sender, beneficiary, token, amount, period_days);
+        self.start_deal(deal_id);
+        self.retainers
+            .setter(U64::from(retainer_id))
+            .period_deals
+            .setter(U64::from(period))
+            .set(U64::from(deal_id));
+        
+        self.request_dispute(deal_id, proof)?;
+        
+        evm::log(RetainerPeriodDisputed {
+            retainer_id,
+            period,
+            deal_id,
+        });
+        
+        Ok(deal_id)
+    }
+    
+    // ====================================
+    //          STREAMING DEALS
+    // ====================================
+    
+    /// Create a streaming deal whose escrow vests linearly over `duration` days
+    /// once accepted (send the amount as value for ETH streams)
+    #[payable]
+    pub fn create_streaming_deal(
+        &mut self,
+        beneficiary: Address,
+        token: Address,
+        amount: U256,
+        duration: u64,
+    ) -> Result<u64, MarketplaceError> {
+        let sender = msg_sender();
+        
+        if duration == 0 {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.validate_new_deal(sender, beneficiary, token, amount)?;
+        self.deposit(sender, token, amount)?;
+        
+        let deal_id = self.record_deal(sender, beneficiary, token, amount, duration);
+        self.deals.setter(U256::from(deal_id)).streaming.set(true);
+        
+        evm::log(StreamCreated { deal_id });
+        
+        Ok(deal_id)
+    }
+    
+    /// Withdraw the vested, not yet withdrawn part of a stream (only beneficiary)
+    pub fn withdraw_streamed(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (token, amount, streamed) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            if !deal.streaming.get() || !deal.is_open() {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != deal.beneficiary.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Disputes freeze the stream until resolved
+            if !deal.accepted.get() || deal.disputed.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (deal.token.get(), deal.amount.get(), deal.streamed.get())
+        };
+        
+        let vested = self.vested_amount(deal_id);
+        let claimable = vested - streamed;
+        if claimable == U256::ZERO {
+            return Err(MarketplaceError::InsufficientBalance(InsufficientBalance {}));
+        }
+        
+        // Calculate fee
+        let fee = claimable * self.fee_percent_for(token) / U256::from(100u64);
+        let payout = claimable - fee;
+        self.fund_judge_pool(token, fee);
+        
+        self.credit_beneficiaries(deal_id, sender, token, payout);
+        self.record_volume(deal_id, token, payout);
+        
+        self.deals.setter(U256::from(deal_id)).streamed.set(vested);
+        if vested == amount {
+            self.close_deal(deal_id, DealStatus::Completed, payout)?;
+            evm::log(DealFinalized { deal_id });
+            
+            // Streams pay out on schedule, so they always count as on time
+            let payer = self.deals.get(U256::from(deal_id)).payer.get();
+            self.adjust_reputation(payer, deal_id, ReputationReason::DealCompleted);
+            self.adjust_team_reputation(deal_id, ReputationReason::DealCompleted);
+            self.adjust_team_reputation(deal_id, ReputationReason::DeliveredOnTime);
+            self.issue_credentials(deal_id);
+        } else {
+            let mut deal = self.deals.setter(U256::from(deal_id));
+            let final_payout = deal.final_payout.get();
+            deal.final_payout.set(final_payout + payout);
+        }
+        
+        evm::log(StreamWithdrawn {
+            deal_id,
+            amount: claimable,
+            payout,
+        });
+        
+        Ok(())
+    }
+    
+    /// Cancel a running stream (only payer)
+    /// The vested part goes to the beneficiary, the unvested remainder back to the payer
+    pub fn cancel_stream(&mut self, deal_id: u64) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        let (beneficiary, token, amount, streamed) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            if !deal.streaming.get() || !deal.is_open() {
+                return Err(MarketplaceError::NotFound(NotFound {}));
+            }
+            
+            if sender != deal.payer.get() {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            }
+            
+            // Not accepted streams are rejected with reject_deal
+            if !deal.accepted.get() || deal.disputed.get() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            (deal.beneficiary.get(), deal.token.get(), deal.amount.get(), deal.streamed.get())
+        };
+        
+        let vested = self.vested_amount(deal_id);
+        let owed = vested - streamed;
+        let refund = amount - vested;
+        
+        // Calculate fee on the part still owed to the beneficiary
+        let fee = owed * self.fee_percent_for(token) / U256::from(100u64);
+        self.fund_judge_pool(token, fee);
+        
+        self.credit_beneficiaries(deal_id, beneficiary, token, owed - fee);
+        self.record_volume(deal_id, token, owed - fee);
+        self.credit(sender, token, refund);
+        
+        self.deals.setter(U256::from(deal_id)).streamed.set(vested);
+        self.close_deal(deal_id, DealStatus::Cancelled, owed - fee)?;
+        
+        evm::log(StreamCancelled {
+            deal_id,
+            vested,
+            refund,
+        });
+        evm::log(DealFinalized { deal_id });
+        
+        Ok(())
+    }
+    
+    // ====================================
+    //              RATINGS
+    // ====================================
+    
+    /// Rate the other side of a settled deal from 1 to 5, with the content hash of a review
+    /// The payer rates the beneficiary (every member of a split deal), the beneficiary
+    /// (the lead of a split deal) rates the payer. Each side rates once per deal.
+    pub fn rate_deal(
+        &mut self,
+        deal_id: u64,
+        rating: u8,
+        review_hash: FixedBytes<32>,
+    ) -> Result<(), MarketplaceError> {
+        let sender = msg_sender();
+        
+        if !(1..=5).contains(&rating) {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let (payer, beneficiary, members_count, by_payer) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            
+            if deal.deal_id.get() == U64::ZERO || !deal.status().is_settled() {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            }
+            
+            // Only the parties can rate, once per side
+            let payer = deal.payer.get();
+            let beneficiary = deal.beneficiary.get();
+            let by_payer = if sender == payer {
+                true
+            } else if sender == beneficiary {
+                false
+            } else {
+                return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+            };
+            
+            let given = if by_payer { deal.payer_rating.get() } else { deal.beneficiary_rating.get() };
+            if given != U8::ZERO {
+                return Err(MarketplaceError::AlreadyExists(AlreadyExists {}));
+            }
+            
+            (payer, beneficiary, deal.members_count.get().as_limbs()[0], by_payer)
+        };
+        
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        if by_payer {
+            deal.payer_rating.set(U8::from(rating));
+            deal.payer_review.set(review_hash);
+        } else {
+            deal.beneficiary_rating.set(U8::from(rating));
+            deal.beneficiary_review.set(review_hash);
+        }
+        
+        if !by_payer {
+            self.record_rating(deal_id, sender, payer, rating, review_hash);
+        } else if members_count == 0 {
+            self.record_rating(deal_id, sender, beneficiary, rating, review_hash);
+        } else {
+            for i in 0..members_count {
+                let member = self.deals.get(U256::from(deal_id)).members.get(U256::from(i));
+                self.record_rating(deal_id, sender, member, rating, review_hash);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // ====================================
+    //            ATTESTATIONS
+    // ====================================
+    
+    /// Attest the caller's current track record as EIP-712 typed data (see `signature::attestation`)
+    /// The digest is recorded so other contracts can verify it through `isValidSignature`,
+    /// and replaces the caller's previous attestation, which stops verifying.
+    /// Verifiers should still enforce a maximum age on `issued_at`.
+    /// Volume is in the default token. Returns the attestation digest.
+    pub fn attest_reputation(&mut self, user_address: Address) -> Result<FixedBytes<32>, MarketplaceError> {
+        // Only the subject decides when their record is attested
+        if msg_sender() != user_address {
+            return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+        }
+        
+        if self.users.get(user_address).user_address.get() == Address::ZERO {
+            return Err(MarketplaceError::NotFound(NotFound {}));
+        }
+        
+        let attestation = self.reputation_attestation(user_address);
+        let digest = attestation.digest(block::chainid(), contract::address());
+        
+        // Superseded attestations no longer verify
+        let previous = self.latest_attestations.get(user_address);
+        self.attestations.setter(previous).set(false);
+        self.attestations.setter(digest).set(true);
+        self.latest_attestations.setter(user_address).set(digest);
+        
+        evm::log(ReputationAttested {
+            user: attestation.user,
+            digest,
+            completed_deals: attestation.completed_deals,
+            volume: attestation.volume,
+            disputes_won: attestation.disputes_won,
+            disputes_lost: attestation.disputes_lost,
+            ratings_count: attestation.ratings_count,
+            rating_average: attestation.rating_average,
+            reputation: attestation.reputation,
+            issued_at: attestation.issued_at,
+        });
+        
+        Ok(digest)
+    }
+    
+    /// ERC-1271: the Marketplace vouches for each user's latest attestation digest
+    pub fn is_valid_signature(&self, hash: FixedBytes<32>, _signature: Bytes) -> FixedBytes<4> {
+        if self.attestations.get(hash) {
+            ERC1271_MAGIC_VALUE
+        } else {
+            FixedBytes::from([0xff; 4])
+        }
+    }
+    
+    /// EIP-712 domain separator of reputation attestations
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn attestation_domain_separator(&self) -> FixedBytes<32> {
+        domain_separator(ATTESTATION_DOMAIN_NAME, ATTESTATION_DOMAIN_VERSION)
+    }
+    
+    // ====================================
+    //        VIEW FUNCTIONS
+    // ====================================
+    
+    /// Get protocol address
+    pub fn protocol_address(&self) -> Address {
+        self.protocol.get()
+    }
+    
+    /// Check if the marketplace is paused
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+    
+    /// Get deal ID counter
+    pub fn deal_id_counter(&self) -> u64 {
+        u64::from_le_bytes(self.deal_id_counter.get().to_le_bytes())
+    }
+    
+    /// Check if a token can be used for new deals
+    pub fn is_token_allowed(&self, token: Address) -> bool {
+        self.tokens.get(token).allowed.get()
+    }
+    
+    /// Get the fee percentage charged on deals paid with `token`
+    pub fn token_fee_percent(&self, token: Address) -> u8 {
+        let config = self.tokens.get(token);
+        let fee_percent = if config.custom_fee.get() {
+            config.fee_percent.get()
+        } else {
+            self.fee_percent.get()
+        };
+        u8::from_le_bytes(fee_percent.to_le_bytes())
+    }
+    
+    /// Get the dispute rate of `token`, see `set_token_dispute_rate`
+    pub fn token_dispute_rate(&self, token: Address) -> U256 {
+        self.tokens.get(token).dispute_rate.get()
+    }
+    
+    /// Get a user's withdrawable balance in a given token
+    pub fn get_balance(&self, user_address: Address, token: Address) -> U256 {
+        let user = self.users.get(user_address);
+        let legacy = if token == self.usdc_token.get() { user.balance.get() } else { U256::ZERO };
+        user.balances.get(token) + legacy
+    }
+    
+    /// Get user info (balance is in the default token, reputations are decayed fixed-point scores)
+    /// Judge status and reputation are read from the protocol
+    pub fn get_user(&self, user_address: Address) -> (Address, U256, i128, i128, bool, bool, bool) {
+        let (is_judge, judge_reputation) = self.protocol_judge(user_address);
+        let balance = self.get_balance(user_address, self.usdc_token.get());
+        let user = self.users.get(user_address);
+        (
+            user.user_address.get(),
+            balance,
+            user.score_as_user.current(),
+            judge_reputation,
+            user.is_payer.get(),
+            user.is_beneficiary.get(),
+            is_judge,
+        )
+    }
+    
+    /// Get deal info
+    /// Closed deals keep their record, see DealStatus for `status`
+    /// Returns (deal_id, payer, beneficiary, amount, started_at, duration, status, token, closed_at, final_payout)
+    pub fn get_deal(&self, deal_id: u64) -> (u64, Address, Address, U256, U256, u64, u8, Address, U256, U256) {
+        let deal = self.deals.get(U256::from(deal_id));
+        (
+            u64::from_le_bytes(deal.deal_id.get().to_le_bytes()),
+            deal.payer.get(),
+            deal.beneficiary.get(),
+            deal.amount.get(),
+            deal.started_at.get(),
+            u64::from_le_bytes(deal.duration.get().to_le_bytes()),
+            deal.status() as u8,
+            deal.token.get(),
+            deal.closed_at.get(),
+            deal.final_payout.get(),
+        )
+    }
+    
+    /// Get how a user's reputation was built (see ReputationReason)
+    /// Returns (decayed fixed-point reputation, completed, delivered_on_time, expired, rejected,
+    /// disputes_won, disputes_lost)
+    pub fn get_reputation_breakdown(&self, user_address: Address) -> (i128, u64, u64, u64, u64, u64, u64) {
+        let user = self.users.get(user_address);
+        let count = |reason: ReputationReason| {
+            u64::from_le_bytes(user.reputation_events.get(U8::from(reason as u8)).to_le_bytes())
+        };
+        (
+            user.score_as_user.current(),
+            count(ReputationReason::DealCompleted),
+            count(ReputationReason::DeliveredOnTime),
+            count(ReputationReason::DealExpired),
+            count(ReputationReason::DealRejected),
+            count(ReputationReason::DisputeWon),
+            count(ReputationReason::DisputeLost),
+        )
+    }
+    
+    /// Get the status of a deal (see DealStatus)
+    pub fn get_deal_status(&self, deal_id: u64) -> u8 {
+        self.deals.get(U256::from(deal_id)).status() as u8
+    }
+    
+    /// Get the number of deals a user took part in, whatever their status
+    pub fn get_user_deals_count(&self, user_address: Address) -> u64 {
+        self.users.get(user_address).deals_count.get().as_limbs()[0]
+    }
+    
+    /// Get a page of a user's deal IDs, oldest first
+    /// `offset` and `limit` count deals matching `status_filter` (a DealStatus, or 255
+    /// for any status), `limit` is capped at 100
+    pub fn get_user_deals(
+        &self,
+        user_address: Address,
+        offset: u64,
+        limit: u64,
+        status_filter: u8,
+    ) -> Result<Vec<u64>, MarketplaceError> {
+        if status_filter > DealStatus::Expired as u8 && status_filter != DEAL_STATUS_ANY {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        let user = self.users.get(user_address);
+        let count = user.deals_count.get().as_limbs()[0];
+        let limit = limit.min(MAX_PAGE_SIZE);
+        
+        let mut deal_ids = Vec::new();
+        let mut skipped = 0u64;
+        for i in 0..count {
+            if deal_ids.len() as u64 == limit {
+                break;
+            }
+            
+            let deal_id = u64::from_le_bytes(user.deals.get(U256::from(i)).to_le_bytes());
+            if status_filter != DEAL_STATUS_ANY && self.get_deal_status(deal_id) != status_filter {
+                continue;
+            }
+            
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            deal_ids.push(deal_id);
+        }
+        
+        Ok(deal_ids)
+    }
+    
+    /// Get job ID counter
+    pub fn job_id_counter(&self) -> u64 {
+        u64::from_le_bytes(self.job_id_counter.get().to_le_bytes())
+    }
+    
+    /// Get job info
+    /// Returns (payer, token, budget, duration, terms_hash, deadline, open, awarded, deal_id, bids_count)
+    pub fn get_job(&self, job_id: u64) -> (Address, Address, U256, u64, FixedBytes<32>, U256, bool, bool, u64, u64) {
+        let job = self.jobs.get(U64::from(job_id));
+        (
+            job.payer.get(),
+            job.token.get(),
+            job.budget.get(),
+            u64::from_le_bytes(job.duration.get().to_le_bytes()),
+            job.terms_hash.get(),
+            job.deadline.get(),
+            job.open.get(),
+            job.awarded.get(),
+            u64::from_le_bytes(job.deal_id.get().to_le_bytes()),
+            u64::from_le_bytes(job.bids_count.get().to_le_bytes()),
+        )
+    }
+    
+    /// Get bid info
+    /// Returns (bidder, price, duration, proposal_hash, withdrawn)
+    pub fn get_bid(&self, job_id: u64, bid_index: u64) -> (Address, U256, u64, FixedBytes<32>, bool) {
+        let job = self.jobs.get(U64::from(job_id));
+        let bid = job.bids.get(U64::from(bid_index));
+        (
+            bid.bidder.get(),
+            bid.price.get(),
+            u64::from_le_bytes(bid.duration.get().to_le_bytes()),
+            bid.proposal_hash.get(),
+            bid.withdrawn.get(),
+        )
+    }
+    
+    /// Get sealed auction settings
+    /// Returns (sealed, reveal_deadline, bid_bond, reputation_weight)
+    pub fn get_auction(&self, job_id: u64) -> (bool, U256, U256, u8) {
+        let job = self.jobs.get(U64::from(job_id));
+        (
+            job.sealed.get(),
+            job.reveal_deadline.get(),
+            job.bid_bond.get(),
+            u8::from_le_bytes(job.reputation_weight.get().to_le_bytes()),
+        )
+    }
+    
+    /// Get sealed bid state
+    /// Returns (commitment, revealed)
+    pub fn get_sealed_bid(&self, job_id: u64, bid_index: u64) -> (FixedBytes<32>, bool) {
+        let job = self.jobs.get(U64::from(job_id));
+        let bid = job.bids.get(U64::from(bid_index));
+        (bid.commitment.get(), bid.revealed.get())
+    }
+    
+    /// Get retainer info
+    /// Returns (payer, beneficiary, token, amount_per_period, period_days, periods,
+    /// notice_periods, claimed_periods, started_at, accepted, stopped)
+    pub fn get_retainer(
+        &self,
+        retainer_id: u64,
+    ) -> (Address, Address, Address, U256, u64, u64, u64, u64, U256, bool, bool) {
+        let retainer = self.retainers.get(U64::from(retainer_id));
+        (
+            retainer.payer.get(),
+            retainer.beneficiary.get(),
+            retainer.token.get(),
+            retainer.amount_per_period.get(),
+            u64::from_le_bytes(retainer.period_days.get().to_le_bytes()),
+            u64::from_le_bytes(retainer.periods.get().to_le_bytes()),
+            u64::from_le_bytes(retainer.notice_periods.get().to_le_bytes()),
+            u64::from_le_bytes(retainer.claimed_periods.get().to_le_bytes()),
+            retainer.started_at.get(),
+            retainer.accepted.get(),
+            retainer.stopped.get(),
+        )
+    }
+    
+    /// Get the deal a disputed retainer period was moved to (0 if none)
+    pub fn get_retainer_period_deal(&self, retainer_id: u64, period: u64) -> u64 {
+        let retainer = self.retainers.get(U64::from(retainer_id));
+        u64::from_le_bytes(retainer.period_deals.get(U64::from(period)).to_le_bytes())
+    }
+    
+    /// Get the members of a split deal
+    /// Returns (members, shares_bps, lead_accepts, accepted_count)
+    pub fn get_split(&self, deal_id: u64) -> (Vec<Address>, Vec<u16>, bool, u64) {
+        let deal = self.deals.get(U256::from(deal_id));
+        let count = deal.members_count.get().as_limbs()[0];
+        let mut members = Vec::new();
+        let mut shares = Vec::new();
+        for i in 0..count {
+            members.push(deal.members.get(U256::from(i)));
+            shares.push(u16::from_le_bytes(deal.member_shares.get(U256::from(i)).to_le_bytes()));
+        }
+        (
+            members,
+            shares,
+            deal.lead_accepts.get(),
+            deal.accepted_count.get().as_limbs()[0],
+        )
+    }
+    
+    /// Get subcontracting info
+    /// Returns (parent_id, open_children)
+    pub fn get_sub_deal(&self, deal_id: u64) -> (u64, u64) {
+        let deal = self.deals.get(U256::from(deal_id));
+        (
+            u64::from_le_bytes(deal.parent_id.get().to_le_bytes()),
+            u64::from_le_bytes(deal.open_children.get().to_le_bytes()),
+        )
+    }
+    
+    /// Check if a deal was paid out (completed deals can still be tipped)
+    pub fn is_deal_completed(&self, deal_id: u64) -> bool {
+        let deal = self.deals.get(U256::from(deal_id));
+        deal.deal_id.get() != U64::ZERO && deal.status().is_settled()
+    }
+    
+    /// Get the fee percentage charged on tips
+    pub fn tip_fee_percent(&self) -> u8 {
+        u8::from_le_bytes(self.tip_fee_percent.get().to_le_bytes())
+    }
+    
+    /// Get the share of fees routed to the judge reward pool, in percent
+    pub fn judge_pool_percent(&self) -> u8 {
+        u8::from_le_bytes(self.judge_pool_percent.get().to_le_bytes())
+    }
+    
+    /// Get the address a deal's beneficiary payout is credited to
+    /// This is the position holder for deals with a position, the beneficiary otherwise
+    pub fn get_claim_holder(&self, deal_id: u64) -> Address {
+        let beneficiary = self.deals.get(U256::from(deal_id)).beneficiary.get();
+        self.claim_holder(deal_id, beneficiary)
+    }
+    
+    /// Get the deal payouts a user earned as a beneficiary in a token, net of fees
+    pub fn get_user_volume(&self, user_address: Address, token: Address) -> U256 {
+        self.users.get(user_address).volume.get(token)
+    }
+    
+    /// Get the ratings left on a deal
+    /// Returns (payer_rating, payer_review, beneficiary_rating, beneficiary_review), 0 if not rated
+    pub fn get_deal_ratings(&self, deal_id: u64) -> (u8, FixedBytes<32>, u8, FixedBytes<32>) {
+        let deal = self.deals.get(U256::from(deal_id));
+        (
+            u8::from_le_bytes(deal.payer_rating.get().to_le_bytes()),
+            deal.payer_review.get(),
+            u8::from_le_bytes(deal.beneficiary_rating.get().to_le_bytes()),
+            deal.beneficiary_review.get(),
+        )
+    }
+    
+    /// Get the ratings a user received
+    /// Returns (ratings_count, average rating in hundredths, 0 without ratings)
+    pub fn get_user_rating(&self, user_address: Address) -> (u64, u64) {
+        let user = self.users.get(user_address);
+        let count = u64::from_le_bytes(user.ratings_count.get().to_le_bytes());
+        let sum = u64::from_le_bytes(user.ratings_sum.get().to_le_bytes());
+        if count == 0 {
+            return (0, 0);
+        }
+        (count, sum * 100 / count)
+    }
+    
+    /// Get the tips a user received as a beneficiary
+    /// Returns (tips_count, total tipped in `token`)
+    pub fn get_tip_stats(&self, user_address: Address, token: Address) -> (u64, U256) {
+        let user = self.users.get(user_address);
+        (
+            u64::from_le_bytes(user.tips_count.get().to_le_bytes()),
+            user.tips_total.get(token),
+        )
+    }
+    
+    /// Get stream info
+    /// Returns (streaming, vested, streamed)
+    pub fn get_stream(&self, deal_id: u64) -> (bool, U256, U256) {
+        let deal = self.deals.get(U256::from(deal_id));
+        (deal.streaming.get(), self.vested_amount(deal_id), deal.streamed.get())
+    }
+    
+    /// Get the dispute fee each side would deposit to dispute a deal, in the default token
+    /// Quoted by the protocol from the value still at stake, converted with the token's
+    /// dispute rate for other tokens. Fails for tokens without a rate.
+    pub fn get_dispute_fee_quote(&self, deal_id: u64) -> Result<U256, MarketplaceError> {
+        let disputed_value = {
+            let deal = self.deals.get(U256::from(deal_id));
+            let token = deal.token.get();
+            let remaining = deal.amount.get() - deal.streamed.get();
+            if token == self.usdc_token.get() {
+                remaining
+            } else {
+                let rate = self.tokens.get(token).dispute_rate.get();
+                if rate == U256::ZERO {
+                    return Err(MarketplaceError::InvalidState(InvalidState {}));
+                }
+                remaining.saturating_mul(rate) / U256::from(DISPUTE_RATE_UNIT)
+            }
+        };
+        
+        let protocol = IProtocol::new(self.protocol.get());
+        Ok(protocol.arbitration_cost(self, disputed_value)?)
+    }
+    
+    /// Get a dispute's fee deposits
+    /// Returns (fee, respondent, respondent_paid)
+    pub fn get_dispute_fee(&self, dispute_id: u64) -> (U256, Address, bool) {
+        let dispute = self.disputes.get(U64::from(dispute_id));
+        (dispute.fee.get(), dispute.respondent.get(), dispute.respondent_paid.get())
+    }
+    
+    /// Get the latest dispute opened over a deal, 0 if never disputed or still waiting for the respondent
+    pub fn get_deal_dispute(&self, deal_id: u64) -> u64 {
+        u64::from_le_bytes(self.deals.get(U256::from(deal_id)).dispute_id.get().to_le_bytes())
+    }
+    
+    /// Get the dispute fee deposits of a deal
+    /// Returns (fee per side, respondent_deadline, respondent_paid), the requester pays on request
+    pub fn get_dispute_deposits(&self, deal_id: u64) -> (U256, U256, bool) {
+        let deal = self.deals.get(U256::from(deal_id));
+        (
+            deal.dispute_fee.get(),
+            deal.respondent_deadline.get(),
+            deal.dispute_id.get() != U64::ZERO,
+        )
+    }
+    
+    /// Get dispute info
+    pub fn get_dispute(&self, dispute_id: u64) -> (u64, Address, bool, bool) {
+        let dispute = self.disputes.get(U64::from(dispute_id));
+        (
+            u64::from_le_bytes(dispute.deal_id.get().to_le_bytes()),
+            dispute.requester.get(),
+            dispute.is_open.get(),
+            dispute.waiting_for_judges.get(),
+        )
+    }
+}
+
+impl Marketplace {
+    
+    /// Shared checks for a new deal before any funds move
+    fn validate_new_deal(
+        &self,
+        payer: Address,
+        beneficiary: Address,
+        token: Address,
+        amount: U256,
+    ) -> Result<(), MarketplaceError> {
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        // Validate inputs
+        if amount == U256::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if beneficiary == Address::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if !self.tokens.get(token).allowed.get() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        // Check user roles - payer must be registered as payer (buyer)
+        let payer_user = self.users.get(payer);
+        if !payer_user.is_payer.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        // Beneficiary must be registered as beneficiary (seller)
+        let beneficiary_user = self.users.get(beneficiary);
+        if !beneficiary_user.is_beneficiary.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        Ok(())
+    }
+    
+    /// Shared checks and storage for a new job posting
+    fn open_job(
+        &mut self,
+        payer: Address,
+        token: Address,
+        budget: U256,
+        duration: u64,
+        terms_hash: FixedBytes<32>,
+        deadline: U256,
+    ) -> Result<u64, MarketplaceError> {
+        if self.paused.get() {
+            return Err(MarketplaceError::ContractPaused(ContractPaused {}));
+        }
+        
+        // Validate inputs
+        if budget == U256::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if deadline <= U256::from(block::timestamp()) {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        if !self.tokens.get(token).allowed.get() {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        // Only payers can post jobs
+        if !self.users.get(payer).is_payer.get() {
+            return Err(MarketplaceError::InvalidState(InvalidState {}));
+        }
+        
+        let job_id = self.job_id_counter.get();
+        let job_id_u64 = u64::from_le_bytes(job_id.to_le_bytes());
+        
+        let mut job = self.jobs.setter(job_id);
+        job.job_id.set(job_id);
+        job.payer.set(payer);
+        job.token.set(token);
+        job.budget.set(budget);
+        job.duration.set(U64::from(duration));
+        job.terms_hash.set(terms_hash);
+        job.deadline.set(deadline);
+        job.open.set(true);
+        
+        self.job_id_counter.set(job_id + U64::from(1));
+        
+        evm::log(JobPosted {
+            job_id: job_id_u64,
+            payer,
+            token,
+            budget,
+            deadline,
+        });
+        
+        Ok(job_id_u64)
+    }
+    
+    /// Index of the running period of an accepted retainer (may be past the last one)
+    fn retainer_current_period(&self, retainer_id: u64) -> u64 {
+        let retainer = self.retainers.get(U64::from(retainer_id));
+        let period_length = U256::from(retainer.period_days.get()) * ONE_DAY;
+        let elapsed_time = U256::from(block::timestamp()) - retainer.started_at.get();
+        u64::try_from(elapsed_time / period_length).unwrap_or(u64::MAX)
+    }
+    
+    /// Number of fully elapsed periods of an accepted retainer, capped to its funded periods
+    fn retainer_elapsed_periods(&self, retainer_id: u64) -> u64 {
+        let periods = u64::from_le_bytes(self.retainers.get(U64::from(retainer_id)).periods.get().to_le_bytes());
+        self.retainer_current_period(retainer_id).min(periods)
+    }
+    
+    /// Part of a streaming deal's escrow vested so far
+    fn vested_amount(&self, deal_id: u64) -> U256 {
+        let deal = self.deals.get(U256::from(deal_id));
+        if !deal.streaming.get() || !deal.accepted.get() {
+            return U256::ZERO;
+        }
+        if !deal.status().is_open() {
+            return deal.streamed.get();
+        }
+        
+        let amount = deal.amount.get();
+        let total_time = U256::from(deal.duration.get()) * ONE_DAY;
+        let elapsed = U256::from(block::timestamp()) - deal.started_at.get();
+        if elapsed >= total_time {
+            return amount;
+        }
+        amount * elapsed / total_time
+    }
+    
+    /// Move a deal to a final status, keeping its record
+    /// `payout` is the net amount credited to the beneficiary side by this settlement
+    fn close_deal(&mut self, deal_id: u64, status: DealStatus, payout: U256) -> Result<(), MarketplaceError> {
+        // Settled sub-deals no longer hold their parent back
+        let parent_id = self.deals.get(U256::from(deal_id)).parent_id.get();
+        if parent_id != U64::ZERO {
+            let mut parent = self.deals.setter(U256::from(parent_id));
+            let Some(open_children) = parent.open_children.get().checked_sub(U64::from(1)) else {
+                return Err(MarketplaceError::InvalidState(InvalidState {}));
+            };
+            parent.open_children.set(open_children);
+        }
+        
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        let final_payout = deal.final_payout.get();
+        deal.status.set(U8::from(status as u8));
+        deal.closed_at.set(U256::from(block::timestamp()));
+        deal.final_payout.set(final_payout + payout);
+        
+        // The claim is paid out, its position is spent
+        if deal.has_position.get() {
+            let collection = ILancerPositions::new(self.positions.get());
+            let call = Call::new_in(self);
+            let _ = collection.burn(call, U256::from(deal_id));
+        }
+        
+        Ok(())
+    }
+    
+    /// Append a deal to a user's deal index
+    /// Entries are never removed, closed deals keep their record and status
+    fn index_deal(&mut self, user_address: Address, deal_id: u64) {
+        let mut user = self.users.setter(user_address);
+        let deals_count = user.deals_count.get();
+        user.deals.setter(deals_count).set(U64::from(deal_id));
+        user.deals_count.set(deals_count + U256::from(1));
+    }
+    
+    /// Mint a completed deal credential to a deal's beneficiary, or every member of a split deal
+    /// Issued whenever the beneficiary side is paid for the work: completed, expired or won dispute
+    /// Failures are ignored so a misconfigured collection never blocks a payout
+    fn issue_credentials(&mut self, deal_id: u64) {
+        let credentials = self.credentials.get();
+        if credentials == Address::ZERO {
+            return;
+        }
+        
+        let (beneficiary, token, amount, members_count) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            (deal.beneficiary.get(), deal.token.get(), deal.amount.get(), deal.members_count.get().as_limbs()[0])
+        };
+        
+        // Buckets are only comparable in the default token
+        let bucket = if token == self.usdc_token.get() { amount_bucket(amount) } else { 0 };
+        
+        let mut recipients = Vec::new();
+        if members_count == 0 {
+            recipients.push(beneficiary);
+        }
+        for i in 0..members_count {
+            recipients.push(self.deals.get(U256::from(deal_id)).members.get(U256::from(i)));
+        }
+        
+        for recipient in recipients {
+            let collection = ILancerCredentials::new(credentials);
+            let call = Call::new_in(self);
+            let _ = collection.mint(call, recipient, CREDENTIAL_COMPLETED_DEAL, deal_id, bucket);
+        }
+    }
+    
+    /// Register a judge with the protocol
+    fn register_judge(&mut self, judge: Address) -> Result<(), MarketplaceError> {
+        let protocol = IProtocol::new(self.protocol.get());
+        let call = Call::new_in(self);
+        protocol.register_judge_for(call, judge)?;
+        Ok(())
+    }
+    
+    /// Judge status and decayed reputation of an address, as recorded by the protocol
+    fn protocol_judge(&self, judge: Address) -> (bool, i128) {
+        let protocol = IProtocol::new(self.protocol.get());
+        match protocol.get_judge(self, judge) {
+            Ok((judge_address, _, reputation, _, _, _)) => (judge_address != Address::ZERO, reputation),
+            Err(_) => (false, 0),
+        }
+    }
+    
+    /// Add a rating to a user's running average
+    fn record_rating(&mut self, deal_id: u64, rater: Address, rated: Address, rating: u8, review_hash: FixedBytes<32>) {
+        let mut user = self.users.setter(rated);
+        let ratings_count = user.ratings_count.get();
+        user.ratings_count.set(ratings_count + U64::from(1));
+        let ratings_sum = user.ratings_sum.get();
+        user.ratings_sum.set(ratings_sum + U64::from(rating));
+        
+        evm::log(DealRated {
+            deal_id,
+            rater,
+            rated,
+            rating,
+            review_hash,
+        });
+    }
+    
+    /// Apply a reputation change to a user for a deal outcome
+    fn adjust_reputation(&mut self, user_address: Address, deal_id: u64, reason: ReputationReason) {
+        let points = reason.points();
+        let mut user = self.users.setter(user_address);
+        let reputation = user.score_as_user.add_points(points);
+        
+        let key = U8::from(reason as u8);
+        let events = user.reputation_events.get(key);
+        user.reputation_events.setter(key).set(events + U64::from(1));
+        
+        evm::log(ReputationChanged {
+            user: user_address,
+            deal_id,
+            reason: reason as u8,
+            points,
+            reputation,
+        });
+    }
+    
+    /// Apply a reputation change to a deal's beneficiary, or every member of a split deal
+    fn adjust_team_reputation(&mut self, deal_id: u64, reason: ReputationReason) {
+        let (beneficiary, members_count) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            (deal.beneficiary.get(), deal.members_count.get().as_limbs()[0])
+        };
+        
+        if members_count == 0 {
+            self.adjust_reputation(beneficiary, deal_id, reason);
+            return;
+        }
+        
+        for i in 0..members_count {
+            let member = self.deals.get(U256::from(deal_id)).members.get(U256::from(i));
+            self.adjust_reputation(member, deal_id, reason);
+        }
+    }
+    
+    /// Whether any deal above this one in a subcontracting chain is disputed
+    fn ancestor_disputed(&self, deal_id: u64) -> bool {
+        let mut parent_id = self.deals.get(U256::from(deal_id)).parent_id.get();
+        while parent_id != U64::ZERO {
+            let parent = self.deals.get(U256::from(parent_id));
+            if parent.status() == DealStatus::Disputed {
+                return true;
+            }
+            parent_id = parent.parent_id.get();
+        }
+        false
+    }
+    
+    /// Escrow `amount` of `token` from `payer`
+    /// ETH must arrive as the call value, ERC-20 tokens are pulled with transferFrom
+    fn deposit(&mut self, payer: Address, token: Address, amount: U256) -> Result<(), MarketplaceError> {
+        if token == NATIVE_TOKEN {
+            if msg::value() != amount {
+                return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+            }
+            return Ok(());
+        }
+        
+        // ETH sent alongside an ERC-20 deposit would be stuck
+        if msg::value() != U256::ZERO {
+            return Err(MarketplaceError::InvalidInput(InvalidInput {}));
+        }
+        
+        safe_transfer_from(token, payer, contract::address(), amount)
+    }
+    
+    /// Mark a deal as accepted and start its clock
+    fn start_deal(&mut self, deal_id: u64) {
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        deal.accepted.set(true);
+        deal.status.set(U8::from(DealStatus::Accepted as u8));
+        deal.started_at.set(U256::from(block::timestamp()));
+        
+        self.mint_position(deal_id);
+        
+        evm::log(DealAccepted { deal_id });
+    }
+    
+    /// Mint the position of a newly accepted deal to its beneficiary
+    /// Streams and split deals pay out as they go or per member, and keep their beneficiaries
+    fn mint_position(&mut self, deal_id: u64) {
+        let positions = self.positions.get();
+        if positions == Address::ZERO {
+            return;
+        }
+        
+        let (beneficiary, streaming, members_count) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            (deal.beneficiary.get(), deal.streaming.get(), deal.members_count.get())
+        };
+        if streaming || members_count != U256::ZERO {
+            return;
+        }
+        
+        // A failed mint leaves the claim with the beneficiary
+        let collection = ILancerPositions::new(positions);
+        let call = Call::new_in(self);
+        if collection.mint(call, beneficiary, U256::from(deal_id)).is_ok() {
+            self.deals.setter(U256::from(deal_id)).has_position.set(true);
+        }
+    }
+    
+    /// Address owed the beneficiary's payout: the position holder, or `beneficiary` without a position
+    fn claim_holder(&self, deal_id: u64, beneficiary: Address) -> Address {
+        if !self.deals.get(U256::from(deal_id)).has_position.get() {
+            return beneficiary;
+        }
+        
+        let collection = ILancerPositions::new(self.positions.get());
+        collection.owner_of(self, U256::from(deal_id)).unwrap_or(beneficiary)
+    }
+    
+    /// Store a new (not yet accepted) deal whose funds are already escrowed
+    fn record_deal(
+        &mut self,
+        payer: Address,
+        beneficiary: Address,
+        token: Address,
+        amount: U256,
+        duration: u64,
+    ) -> u64 {
+        // Get current deal ID
+        let deal_id = self.deal_id_counter.get();
+        
+        // Create deal - payer is the buyer, beneficiary is the seller
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        deal.deal_id.set(U64::from(deal_id));
+        deal.payer.set(payer);
+        deal.beneficiary.set(beneficiary);
+        deal.amount.set(amount);
+        deal.duration.set(U64::from(duration));
+        deal.started_at.set(U256::ZERO);
+        deal.accepted.set(false);
+        deal.disputed.set(false);
+        deal.token.set(token);
+        deal.status.set(U8::from(DealStatus::Proposed as u8));
+        
+        let deal_id_u64 = u64::from_le_bytes(deal_id.to_le_bytes());
+        
+        // Index the deal under both parties
+        self.index_deal(payer, deal_id_u64);
+        if beneficiary != payer {
+            self.index_deal(beneficiary, deal_id_u64);
+        }
+        evm::log(DealCreated {
+            deal_id: deal_id_u64,
+            payer,
+            beneficiary,
+            token,
+            amount,
+        });
+        
+        // Increment counter
+        self.deal_id_counter.set(deal_id + U64::from(1));
+        
+        deal_id_u64
+    }
+    
+    /// Submit an EIP-2612 permit letting this contract spend `value` of `owner`'s tokens
+    /// Failures are ignored: a front-run permit leaves the allowance in place, and a
+    /// missing allowance still makes the following transferFrom revert
+    #[allow(clippy::too_many_arguments)]
+    fn try_permit(
+        &mut self,
+        token: Address,
+        owner: Address,
+        value: U256,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) {
+        let erc20 = IERC20Permit::new(token);
+        let call = Call::new_in(self);
+        let _ = erc20.permit(call, owner, contract::address(), value, deadline, v, r, s);
+    }
+    
+    /// Fee percentage for `token` as a U256, ready for payout maths
+    fn fee_percent_for(&self, token: Address) -> U256 {
+        U256::from(self.token_fee_percent(token))
+    }
+    
+    /// Credit a deal payout to its beneficiary, or pro-rata to every member of a split deal
+    /// Rounding dust goes to the lead (`beneficiary`)
+    fn credit_beneficiaries(&mut self, deal_id: u64, beneficiary: Address, token: Address, amount: U256) {
+        let members_count = self.deals.get(U256::from(deal_id)).members_count.get().as_limbs()[0];
+        
+        let mut remaining = amount;
+        for i in 1..members_count {
+            let (member, share) = {
+                let deal = self.deals.get(U256::from(deal_id));
+                (deal.members.get(U256::from(i)), U256::from(deal.member_shares.get(U256::from(i))))
+            };
+            let member_amount = amount * share / U256::from(BPS_DENOMINATOR);
+            remaining -= member_amount;
+            self.credit(member, token, member_amount);
+        }
+        
+        self.credit(beneficiary, token, remaining);
+    }
+    
+    /// Add a deal payout to the volume of its beneficiary, or pro-rata to every member of a split deal
+    /// Volume follows the work, so it goes to the beneficiary even when a position holder is paid
+    fn record_volume(&mut self, deal_id: u64, token: Address, amount: U256) {
+        let (beneficiary, members_count) = {
+            let deal = self.deals.get(U256::from(deal_id));
+            (deal.beneficiary.get(), deal.members_count.get().as_limbs()[0])
+        };
+        
+        let mut remaining = amount;
+        for i in 1..members_count {
+            let (member, share) = {
+                let deal = self.deals.get(U256::from(deal_id));
+                (deal.members.get(U256::from(i)), U256::from(deal.member_shares.get(U256::from(i))))
+            };
+            let member_amount = amount * share / U256::from(BPS_DENOMINATOR);
+            remaining -= member_amount;
+            self.add_volume(member, token, member_amount);
+        }
+        
+        self.add_volume(beneficiary, token, remaining);
+    }
+    
+    /// Add to a user's volume in a given token
+    fn add_volume(&mut self, user: Address, token: Address, amount: U256) {
+        let mut user_mut = self.users.setter(user);
+        let volume = user_mut.volume.get(token);
+        user_mut.volume.setter(token).set(volume + amount);
+    }
+    
+    /// Snapshot of a user's track record for an attestation
+    fn reputation_attestation(&self, user_address: Address) -> ReputationAttestation {
+        let (ratings_count, rating_average) = self.get_user_rating(user_address);
+        let user = self.users.get(user_address);
+        let events = |reason: ReputationReason| {
+            u64::from_le_bytes(user.reputation_events.get(U8::from(reason as u8)).to_le_bytes())
+        };
+        
+        ReputationAttestation {
+            user: user_address,
+            completed_deals: events(ReputationReason::DealCompleted),
+            volume: user.volume.get(self.usdc_token.get()),
+            disputes_won: events(ReputationReason::DisputeWon),
+            disputes_lost: events(ReputationReason::DisputeLost),
+            ratings_count,
+            rating_average,
+            reputation: user.score_as_user.current(),
+            issued_at: block::timestamp(),
+        }
+    }
+    
+    /// Route the judge pool share of a fee to the protocol's reward pool
+    /// The protocol pulls the share itself, so the transfer and the pool credit succeed or
+    /// fail together. Failures are ignored so the pool never blocks a payout, the share then
+    /// stays in the marketplace with the rest of the fee
+    fn fund_judge_pool(&mut self, token: Address, fee: U256) {
+        if token != self.usdc_token.get() {
+            return;
+        }
+        
+        let amount = fee * U256::from(self.judge_pool_percent.get()) / U256::from(100u64);
+        if amount == U256::ZERO {
+            return;
+        }
+        
+        let protocol_addr = self.protocol.get();
+        let erc20 = IERC20::new(token);
+        let call = Call::new_in(self);
+        if !matches!(erc20.approve(call, protocol_addr, amount), Ok(true)) {
+            return;
+        }
+        
+        let protocol = IProtocol::new(protocol_addr);
+        let call = Call::new_in(self);
+        if protocol.fund_reward_pool(call, amount).is_ok() {
+            evm::log(JudgePoolFunded { token, amount });
+        } else {
+            // Don't leave an allowance behind for the share that wasn't pulled
+            let call = Call::new_in(self);
+            let _ = erc20.approve(call, protocol_addr, U256::ZERO);
+        }
+    }
+    
+    /// Credit a withdrawable balance to a user in a given token
+    fn credit(&mut self, user: Address, token: Address, amount: U256) {
+        self.settle_legacy_balance(user);
+        let mut user_mut = self.users.setter(user);
+        let current_balance = user_mut.balances.get(token);
+        user_mut.balances.setter(token).set(current_balance + amount);
+    }
+    
+    /// Deals `migrate_deals` has not rewritten yet
+    fn pending_deal_migrations(&self) -> u64 {
+        let deal_count = u64::from_le_bytes(self.deal_id_counter.get().to_le_bytes());
+        let cursor = u64::from_le_bytes(self.deal_migration_cursor.get().to_le_bytes()).max(1);
+        deal_count.saturating_sub(cursor)
+    }
+    
+    /// Bring one deal record written under `from_version` up to the current layout
+    fn migrate_deal(&mut self, deal_id: u64, from_version: u64) {
+        let usdc = self.usdc_token.get();
+        let mut deal = self.deals.setter(U256::from(deal_id));
+        if deal.deal_id.get() == U64::ZERO {
+            return;
+        }
+        
+        // v3: v1 deals carry no token and settle in the default token
+        if from_version < 3 && deal.token.get() == Address::ZERO {
+            deal.token.set(usdc);
+        }
+        
+        // v4: v1 deals were cleared once settled, so the remaining records are
+        // open and their status follows the accepted/disputed flags
+        if from_version < 4 && deal.status.get() == U8::ZERO {
+            let status = if deal.disputed.get() {
+                DealStatus::Disputed
+            } else if deal.accepted.get() {
+                DealStatus::Accepted
+            } else {
+                DealStatus::Proposed
+            };
+            deal.status.set(U8::from(status as u8));
+        }
+    }
+    
+    /// Fold a user's v1 balance into their default-token balance
+    fn settle_legacy_balance(&mut self, user: Address) {
+        let usdc = self.usdc_token.get();
+        let mut user_mut = self.users.setter(user);
+        let legacy = user_mut.balance.get();
+        if legacy == U256::ZERO {
+            return;
+        }
+        
+        let current_balance = user_mut.balances.get(usdc);
+        user_mut.balances.setter(usdc).set(current_balance + legacy);
+        user_mut.balance.set(U256::ZERO);
+    }
+    
+    /// Upgrade authorization hook
+    fn authorize_upgrade(&self, _new_implementation: Address) -> Result<(), MarketplaceError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        Ok(())
+    }
+}
+
+/// Size tier of a deal paid in the default token, for credentials
+/// 1: under 100, 2: under 1,000, 3: under 10,000, 4: 10,000 USDC or more
+fn amount_bucket(amount: U256) -> u8 {
+    let unit = U256::from(10u64.pow(USDC_DECIMALS as u32));
+    if amount < unit * U256::from(100u64) {
+        1
+    } else if amount < unit * U256::from(1_000u64) {
+        2
+    } else if amount < unit * U256::from(10_000u64) {
+        3
+    } else {
+        4
+    }
+}
+
+/// Sealed auction score, lower wins
+/// Each reputation point lowers (or, if negative, raises) the price by `weight` percent
+fn bid_score(price: U256, reputation: i128, weight: u8) -> U256 {
+    let hundred = U256::from(100u64);
+    let adjustment = U256::from(weight) * U256::from(reputation.unsigned_abs());
+    if reputation >= 0 {
+        price * hundred / (hundred + adjustment)
+    } else {
+        price * (hundred + adjustment) / hundred
+    }
+}
+
+/// Sealed bid commitment: keccak256(abi.encodePacked(uint64 job_id, address bidder, uint256 price, bytes secret))
+fn bid_commitment(job_id: u64, bidder: Address, price: U256, secret: &[u8]) -> FixedBytes<32> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&job_id.to_be_bytes());
+    data.extend_from_slice(bidder.as_slice());
+    data.extend_from_slice(&price.to_be_bytes::<32>());
+    data.extend_from_slice(secret);
+    keccak(&data)
+}
+
+/// Whether a dispute and a deal record each other, as `match_dispute_fee` links them
+/// Deals disputed before the link was stored record no dispute, their only dispute
+/// is bound by its deal id alone
+fn dispute_matches_deal(dispute_id: u64, deal_id: u64, deal_dispute_id: u64, dispute_deal_id: u64) -> bool {
+    (deal_dispute_id == dispute_id || deal_dispute_id == 0) && dispute_deal_id == deal_id
+}
+
+/// A sub-deal spends the parent's payout, so only its claim holder may carve one,
+/// and only from an accepted deal that settles on its own
+fn check_sub_deal_parent(sender: Address, holder: Address, eligible: bool) -> Result<(), MarketplaceError> {
+    if sender != holder {
+        return Err(MarketplaceError::Unauthorized(Unauthorized {}));
+    }
+    if !eligible {
+        return Err(MarketplaceError::InvalidState(InvalidState {}));
+    }
+    Ok(())
+}
+
+/// ERC-20 `transfer` that also accepts tokens returning no value (such as USDT)
+/// and fails when the token returns `false`
+fn safe_transfer(token: Address, to: Address, amount: U256) -> Result<(), MarketplaceError> {
+    call_token(token, &erc20::transferCall { to, amount }.abi_encode())
+}
+
+/// ERC-20 `transferFrom` with the same return value handling as `safe_transfer`
+fn safe_transfer_from(token: Address, from: Address, to: Address, amount: U256) -> Result<(), MarketplaceError> {
+    call_token(token, &erc20::transferFromCall { from, to, amount }.abi_encode())
+}
+
+/// Call a token, succeeding on empty return data (from a contract) or an encoded `true`
+fn call_token(token: Address, calldata: &[u8]) -> Result<(), MarketplaceError> {
+    let returned = RawCall::new()
+        .call(token, calldata)
+        .map_err(|_| MarketplaceError::CallFailed(CallFailed {}))?;
+
+    if token_call_succeeded(&returned, token.has_code()) {
+        Ok(())
+    } else {
+        Err(MarketplaceError::CallFailed(CallFailed {}))
+    }
+}
+
+/// SafeERC20 rule: no return data counts as success only if the token has code
+fn token_call_succeeded(returned: &[u8], has_code: bool) -> bool {
+    if returned.is_empty() {
+        return has_code;
+    }
+    returned.len() == 32 && returned[..31].iter().all(|byte| *byte == 0) && returned[31] == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_calls_accept_true_or_no_return_value() {
+        let mut encoded_true = [0u8; 32];
+        encoded_true[31] = 1;
+        assert!(token_call_succeeded(&encoded_true, true));
+        assert!(token_call_succeeded(&[], true));
+    }
+
+    #[test]
+    fn token_calls_reject_false_and_malformed_returns() {
+        assert!(!token_call_succeeded(&[0u8; 32], true));
+        assert!(!token_call_succeeded(&[1u8], true));
+
+        let mut dirty_true = [0u8; 32];
+        dirty_true[0] = 1;
+        dirty_true[31] = 1;
+        assert!(!token_call_succeeded(&dirty_true, true));
+    }
+
+    #[test]
+    fn token_calls_to_accounts_without_code_fail() {
+        assert!(!token_call_succeeded(&[], false));
+    }
+    
+    #[test]
+    fn disputes_settle_only_their_own_deal() {
+        assert!(dispute_matches_deal(3, 7, 3, 7));
+        
+        // A ruling on another deal's dispute, or a stale dispute of a re-disputed deal
+        assert!(!dispute_matches_deal(3, 8, 4, 7));
+        assert!(!dispute_matches_deal(3, 7, 4, 7));
+        assert!(!dispute_matches_deal(3, 7, 3, 8));
+    }
+    
+    #[test]
+    fn legacy_disputes_are_bound_by_their_deal() {
+        assert!(dispute_matches_deal(3, 7, 0, 7));
+        assert!(!dispute_matches_deal(3, 8, 0, 7));
+    }
+    
+    #[test]
+    fn sub_deals_are_carved_by_the_claim_holder() {
+        let beneficiary = Address::repeat_byte(1);
+        assert!(check_sub_deal_parent(beneficiary, beneficiary, true).is_ok());
+        
+        // Once the position is sold, the buyer holds the payout and the beneficiary can't spend it
+        let buyer = Address::repeat_byte(2);
+        assert!(matches!(
+            check_sub_deal_parent(beneficiary, buyer, true),
+            Err(MarketplaceError::Unauthorized(_))
+        ));
+        assert!(check_sub_deal_parent(buyer, buyer, true).is_ok());
+    }
+    
+    #[test]
+    fn sub_deals_need_an_eligible_parent() {
+        let beneficiary = Address::repeat_byte(1);
+        assert!(matches!(
+            check_sub_deal_parent(beneficiary, beneficiary, false),
+            Err(MarketplaceError::InvalidState(_))
+        ));
     }
 }
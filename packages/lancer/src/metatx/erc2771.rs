@@ -0,0 +1,91 @@
+//!
+//! Lancer Meta-Transactions - Arbitrum Stylus Implementation
+//!
+//! ERC-2771 support shared by the Marketplace and Protocol contracts, so users
+//! without ETH for gas can have a relayer submit their calls through a trusted
+//! forwarder (see `forwarder`).
+//!
+//! The forwarder appends the signer's address to the calldata. Stylus routers
+//! reject trailing calldata, so the Lancer proxy strips the suffix and stores the
+//! signer in a dedicated slot for the duration of the call. Contracts must use
+//! `msg_sender()` instead of `msg::sender()` to pick it up. Forwarded calls are
+//! therefore only honored when the contract is deployed behind the proxy.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{uint, Address, U256},
+    evm,
+    msg,
+    storage::{GlobalStorage, StorageCache},
+};
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event TrustedForwarderUpdated(address indexed forwarder);
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// keccak256("lancer.metatx.trusted_forwarder") - 1
+pub const TRUSTED_FORWARDER_SLOT: U256 =
+    uint!(0x3faaadd4e3f5b36e597f621c3e36d5742c9f012edd4e3a0752cc15457846dec2_U256);
+
+/// keccak256("lancer.metatx.forwarded_sender") - 1
+pub const FORWARDED_SENDER_SLOT: U256 =
+    uint!(0x1a5ca7249ec64b778c73ae7512dca6cb3e3f45c06c1a589ee4ec8c46d35cea50_U256);
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+/// The forwarder whose appended sender is trusted (zero if disabled)
+pub fn trusted_forwarder() -> Address {
+    Address::from_word(StorageCache::get_word(TRUSTED_FORWARDER_SLOT))
+}
+
+/// Set the trusted forwarder, the zero address disables meta-transactions
+/// The caller is responsible for authorizing the change
+pub fn set_trusted_forwarder(forwarder: Address) {
+    unsafe { StorageCache::set_word(TRUSTED_FORWARDER_SLOT, forwarder.into_word()) };
+    evm::log(TrustedForwarderUpdated { forwarder });
+}
+
+/// Whether `forwarder` is the trusted forwarder (ERC-2771 `isTrustedForwarder`)
+pub fn is_trusted_forwarder(forwarder: Address) -> bool {
+    forwarder != Address::ZERO && forwarder == trusted_forwarder()
+}
+
+/// Signer of the call currently being forwarded, set by the proxy
+pub fn forwarded_sender() -> Address {
+    Address::from_word(StorageCache::get_word(FORWARDED_SENDER_SLOT))
+}
+
+/// Record the signer of the call being forwarded (proxy only)
+pub fn set_forwarded_sender(sender: Address) {
+    unsafe { StorageCache::set_word(FORWARDED_SENDER_SLOT, sender.into_word()) };
+}
+
+/// The account that originated the call: the forwarded signer when called
+/// through the trusted forwarder, `msg::sender()` otherwise
+pub fn msg_sender() -> Address {
+    let sender = msg::sender();
+    if is_trusted_forwarder(sender) {
+        let signer = forwarded_sender();
+        if signer != Address::ZERO {
+            return signer;
+        }
+    }
+    sender
+}
@@ -0,0 +1,2 @@
+pub mod erc2771;
+pub use erc2771::*;
@@ -2,7 +2,7 @@
 //! Mock USDC ERC20 Token - Arbitrum Stylus Implementation
 //!
 //! This contract implements a simple ERC20 token for testing purposes
-//! with USDC-like properties (6 decimals, mintable).
+//! with USDC-like properties (6 decimals, mintable, EIP-2612 permit).
 //!
 //! @author 0xDarioSanchez
 //!
@@ -11,14 +11,18 @@
 
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use stylus_sdk::{
-    alloy_primitives::{Address, U256, U8},
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256, U8},
+    crypto::keccak,
     prelude::*,
+    block,
     evm,
     msg,
 };
 use alloy_sol_types::sol;
+use crate::access::{AccessControl, AccessControlError, ADMIN_ROLE};
+use crate::signature::{self, hash_typed_data, recover};
 
 // ====================================
 //          STORAGE STRUCTS          
@@ -34,7 +38,10 @@ sol_storage! {
         
         mapping(address => uint256) balances;
         mapping(address => mapping(address => uint256)) allowances;
-        address owner;
+        #[borrow]
+        AccessControl access_control;
+        
+        mapping(address => uint256) nonces;  // EIP-2612 permit nonces
     }
 }
 
@@ -50,7 +57,9 @@ sol! {
     error InsufficientBalance();
     error InsufficientAllowance();
     error InvalidAddress();
-    error NotOwner();
+    error Unauthorized();
+    error PermitExpired();
+    error InvalidSignature();
 }
 
 // ====================================
@@ -62,23 +71,42 @@ pub enum MockUSDCError {
     InsufficientBalance(InsufficientBalance),
     InsufficientAllowance(InsufficientAllowance),
     InvalidAddress(InvalidAddress),
-    NotOwner(NotOwner),
+    Unauthorized(Unauthorized),
+    PermitExpired(PermitExpired),
+    InvalidSignature(InvalidSignature),
 }
 
+// Missing roles surface as Unauthorized
+impl From<AccessControlError> for MockUSDCError {
+    fn from(_error: AccessControlError) -> Self {
+        MockUSDCError::Unauthorized(Unauthorized {})
+    }
+}
+
+// ====================================
+//          EIP-2612 CONSTANTS
+// ====================================
+
+// keccak256("Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)")
+const PERMIT_TYPEHASH: FixedBytes<32> =
+    fixed_bytes!("6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9");
+const DOMAIN_VERSION: &str = "1";
+
 // ====================================
-//        IMPLEMENTATION          
+//        IMPLEMENTATION
 // ====================================
 
 #[public]
+#[inherit(AccessControl)]
 impl MockUSDC {
     
-    /// Initialize the token
+    /// Initialize the token, `owner` receives the admin role
     pub fn init(&mut self, owner: Address) -> Result<(), MockUSDCError> {
         self.name.set_str("Mock USDC");
         self.symbol.set_str("USDC");
         self.decimals.set(U8::from(6u8));
         self.total_supply.set(U256::ZERO);
-        self.owner.set(owner);
+        self.access_control._grant_role(ADMIN_ROLE, owner);
         
         Ok(())
     }
@@ -206,17 +234,78 @@ impl MockUSDC {
     }
     
     // ====================================
-    //        MINT FUNCTION (FOR TESTING)
+    //        EIP-2612 PERMIT
     // ====================================
     
-    /// Mint tokens to an account (only owner)
-    pub fn mint(&mut self, to: Address, amount: U256) -> Result<(), MockUSDCError> {
-        let sender = msg::sender();
+    /// Approve `spender` with an off-chain signature from `owner`
+    #[allow(clippy::too_many_arguments)] // Signature fixed by EIP-2612
+    pub fn permit(
+        &mut self,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> Result<(), MockUSDCError> {
+        if U256::from(block::timestamp()) > deadline {
+            return Err(MockUSDCError::PermitExpired(PermitExpired {}));
+        }
+        
+        if spender == Address::ZERO {
+            return Err(MockUSDCError::InvalidAddress(InvalidAddress {}));
+        }
+        
+        let nonce = self.nonces.get(owner);
         
-        if sender != self.owner.get() {
-            return Err(MockUSDCError::NotOwner(NotOwner {}));
+        // hashStruct(Permit)
+        let mut encoded = Vec::with_capacity(6 * 32);
+        encoded.extend_from_slice(PERMIT_TYPEHASH.as_slice());
+        encoded.extend_from_slice(owner.into_word().as_slice());
+        encoded.extend_from_slice(spender.into_word().as_slice());
+        encoded.extend_from_slice(&value.to_be_bytes::<32>());
+        encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+        encoded.extend_from_slice(&deadline.to_be_bytes::<32>());
+        let digest = hash_typed_data(self.domain_separator(), keccak(&encoded));
+        
+        // Signature must come from the token owner
+        let signer = recover(digest, v, r, s);
+        if signer == Address::ZERO || signer != owner {
+            return Err(MockUSDCError::InvalidSignature(InvalidSignature {}));
         }
         
+        self.nonces.setter(owner).set(nonce + U256::from(1));
+        self.allowances.setter(owner).setter(spender).set(value);
+        
+        evm::log(Approval {
+            owner,
+            spender,
+            value,
+        });
+        
+        Ok(())
+    }
+    
+    /// Get the next permit nonce of an account
+    pub fn nonces(&self, owner: Address) -> U256 {
+        self.nonces.get(owner)
+    }
+    
+    /// Get the EIP-712 domain separator used by permit
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn domain_separator(&self) -> FixedBytes<32> {
+        signature::domain_separator(&self.name.get_string(), DOMAIN_VERSION)
+    }
+    
+    // ====================================
+    //        MINT FUNCTION (FOR TESTING)
+    // ====================================
+    
+    /// Mint tokens to an account (only admin)
+    pub fn mint(&mut self, to: Address, amount: U256) -> Result<(), MockUSDCError> {
+        self.access_control.only_role(ADMIN_ROLE, msg::sender())?;
+        
         if to == Address::ZERO {
             return Err(MockUSDCError::InvalidAddress(InvalidAddress {}));
         }
@@ -239,9 +328,4 @@ impl MockUSDC {
         
         Ok(())
     }
-    
-    /// Get owner address
-    pub fn owner(&self) -> Address {
-        self.owner.get()
-    }
 }
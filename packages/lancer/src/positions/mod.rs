@@ -0,0 +1,3 @@
+pub mod positions;
+
+pub use positions::LancerPositions;
@@ -0,0 +1,349 @@
+//!
+//! Lancer Deal Positions - Arbitrum Stylus Implementation
+//!
+//! Transferable ERC-721 tokens representing the beneficiary's claim on an
+//! accepted deal's escrow. The Marketplace mints one per accepted deal (the
+//! token id is the deal id) and credits the payout to whoever holds it when
+//! the deal settles, so the claim can be sold or factored.
+//!
+//! Positions are burned by the Marketplace once their deal is closed.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use alloy_sol_types::sol;
+use crate::access::{AccessControl, AccessControlError, ADMIN_ROLE, MINTER_ROLE};
+use crate::metatx::msg_sender;
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256},
+    call::Call,
+    prelude::*,
+    evm,
+    types::AddressVM,
+};
+
+// ====================================
+//          STORAGE STRUCTS
+// ====================================
+
+sol_storage! {
+    #[entrypoint]
+    pub struct LancerPositions {
+        #[borrow]
+        AccessControl access_control;
+        bool initialized;
+
+        uint256 total_supply;  // Positions currently outstanding
+        mapping(uint256 => address) owners;  // Token id is the deal id
+        mapping(address => uint256) balances;
+        mapping(uint256 => address) token_approvals;
+        mapping(address => mapping(address => bool)) operator_approvals;
+    }
+}
+
+sol_interface! {
+    interface IERC721Receiver {
+        function onERC721Received(address operator, address from, uint256 token_id, bytes data) external returns (bytes4);
+    }
+}
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
+    event Approval(address indexed owner, address indexed approved, uint256 indexed token_id);
+    event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+
+    error Unauthorized();
+    error AlreadyInitialized();
+    error InvalidAddress();
+    error AlreadyExists();
+    error NonexistentToken();
+    error UnsafeRecipient();
+}
+
+// ====================================
+//          ERROR TYPES
+// ====================================
+
+#[derive(SolidityError)]
+pub enum PositionsError {
+    Unauthorized(Unauthorized),
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidAddress(InvalidAddress),
+    AlreadyExists(AlreadyExists),
+    NonexistentToken(NonexistentToken),
+    UnsafeRecipient(UnsafeRecipient),
+}
+
+// Missing roles surface as Unauthorized
+impl From<AccessControlError> for PositionsError {
+    fn from(_error: AccessControlError) -> Self {
+        PositionsError::Unauthorized(Unauthorized {})
+    }
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+const NAME: &str = "Lancer Deal Positions";
+const SYMBOL: &str = "LANCER-POS";
+
+const INTERFACE_ERC165: FixedBytes<4> = fixed_bytes!("01ffc9a7");
+const INTERFACE_ERC721: FixedBytes<4> = fixed_bytes!("80ac58cd");
+const INTERFACE_ERC721_METADATA: FixedBytes<4> = fixed_bytes!("5b5e139f");
+
+/// bytes4(keccak256("onERC721Received(address,address,uint256,bytes)"))
+const ERC721_RECEIVED: FixedBytes<4> = fixed_bytes!("150b7a02");
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+#[public]
+#[inherit(AccessControl)]
+impl LancerPositions {
+
+    /// Initialize the collection, `owner` receives the admin role
+    /// Grant MINTER_ROLE to the Marketplace afterwards
+    pub fn init(&mut self, owner: Address) -> Result<(), PositionsError> {
+        if self.initialized.get() {
+            return Err(PositionsError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+
+        self.initialized.set(true);
+        self.access_control._grant_role(ADMIN_ROLE, owner);
+
+        Ok(())
+    }
+
+    // ====================================
+    //        MINTING / BURNING
+    // ====================================
+
+    /// Mint the position of an accepted deal to its beneficiary (only minter)
+    pub fn mint(&mut self, to: Address, deal_id: U256) -> Result<(), PositionsError> {
+        self.access_control.only_role(MINTER_ROLE, msg_sender())?;
+
+        if to == Address::ZERO {
+            return Err(PositionsError::InvalidAddress(InvalidAddress {}));
+        }
+        if self.owners.get(deal_id) != Address::ZERO {
+            return Err(PositionsError::AlreadyExists(AlreadyExists {}));
+        }
+
+        self.owners.setter(deal_id).set(to);
+        let balance = self.balances.get(to);
+        self.balances.setter(to).set(balance + U256::from(1));
+        let total_supply = self.total_supply.get();
+        self.total_supply.set(total_supply + U256::from(1));
+
+        evm::log(Transfer {
+            from: Address::ZERO,
+            to,
+            token_id: deal_id,
+        });
+
+        Ok(())
+    }
+
+    /// Burn the position of a closed deal (only minter)
+    pub fn burn(&mut self, deal_id: U256) -> Result<(), PositionsError> {
+        self.access_control.only_role(MINTER_ROLE, msg_sender())?;
+
+        let owner = self.owner_of(deal_id)?;
+
+        self.token_approvals.setter(deal_id).set(Address::ZERO);
+        self.owners.setter(deal_id).set(Address::ZERO);
+        let balance = self.balances.get(owner);
+        self.balances.setter(owner).set(balance - U256::from(1));
+        let total_supply = self.total_supply.get();
+        self.total_supply.set(total_supply - U256::from(1));
+
+        evm::log(Transfer {
+            from: owner,
+            to: Address::ZERO,
+            token_id: deal_id,
+        });
+
+        Ok(())
+    }
+
+    // ====================================
+    //        ERC721 FUNCTIONS
+    // ====================================
+
+    /// Get collection name
+    pub fn name(&self) -> String {
+        String::from(NAME)
+    }
+
+    /// Get collection symbol
+    pub fn symbol(&self) -> String {
+        String::from(SYMBOL)
+    }
+
+    /// Get number of outstanding positions
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    /// Get number of positions held by an account
+    pub fn balance_of(&self, owner: Address) -> Result<U256, PositionsError> {
+        if owner == Address::ZERO {
+            return Err(PositionsError::InvalidAddress(InvalidAddress {}));
+        }
+        Ok(self.balances.get(owner))
+    }
+
+    /// Get holder of a deal position
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, PositionsError> {
+        let owner = self.owners.get(token_id);
+        if owner == Address::ZERO {
+            return Err(PositionsError::NonexistentToken(NonexistentToken {}));
+        }
+        Ok(owner)
+    }
+
+    /// Get the JSON metadata of a position, deal terms live in the Marketplace
+    #[selector(name = "tokenURI")]
+    pub fn token_uri(&self, token_id: U256) -> Result<String, PositionsError> {
+        self.owner_of(token_id)?;
+
+        Ok(format!(
+            "data:application/json;utf8,{{\"name\":\"Lancer Deal #{}\",\"description\":\"Claim on the escrowed payout of a Lancer deal\",\"attributes\":[{{\"trait_type\":\"Deal\",\"value\":{}}}]}}",
+            token_id, token_id,
+        ))
+    }
+
+    /// Get the approved address of a position
+    pub fn get_approved(&self, token_id: U256) -> Result<Address, PositionsError> {
+        self.owner_of(token_id)?;
+        Ok(self.token_approvals.get(token_id))
+    }
+
+    /// Check if an operator may transfer every position of an owner
+    pub fn is_approved_for_all(&self, owner: Address, operator: Address) -> bool {
+        self.operator_approvals.getter(owner).get(operator)
+    }
+
+    /// Approve an address to transfer a position (owner or operator)
+    pub fn approve(&mut self, to: Address, token_id: U256) -> Result<(), PositionsError> {
+        let sender = msg_sender();
+        let owner = self.owner_of(token_id)?;
+
+        if sender != owner && !self.is_approved_for_all(owner, sender) {
+            return Err(PositionsError::Unauthorized(Unauthorized {}));
+        }
+
+        self.token_approvals.setter(token_id).set(to);
+        evm::log(Approval {
+            owner,
+            approved: to,
+            token_id,
+        });
+
+        Ok(())
+    }
+
+    /// Approve or revoke an operator for every position of the caller
+    pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<(), PositionsError> {
+        let sender = msg_sender();
+
+        if operator == Address::ZERO {
+            return Err(PositionsError::InvalidAddress(InvalidAddress {}));
+        }
+
+        self.operator_approvals.setter(sender).setter(operator).set(approved);
+        evm::log(ApprovalForAll {
+            owner: sender,
+            operator,
+            approved,
+        });
+
+        Ok(())
+    }
+
+    /// Transfer a position
+    pub fn transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), PositionsError> {
+        self.transfer(from, to, token_id)
+    }
+
+    /// Transfer a position, checking that contract recipients accept it
+    pub fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), PositionsError> {
+        self.safe_transfer_from_with_data(from, to, token_id, Bytes(Vec::new()))
+    }
+
+    /// Transfer a position with data, checking that contract recipients accept it
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Bytes,
+    ) -> Result<(), PositionsError> {
+        self.transfer(from, to, token_id)?;
+
+        if to.has_code() {
+            let receiver = IERC721Receiver::new(to);
+            let operator = msg_sender();
+            let call = Call::new_in(self);
+            match receiver.on_erc_721_received(call, operator, from, token_id, data.0.into()) {
+                Ok(selector) if selector == ERC721_RECEIVED => {}
+                _ => return Err(PositionsError::UnsafeRecipient(UnsafeRecipient {})),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// ERC-165 interface detection
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        interface_id == INTERFACE_ERC165
+            || interface_id == INTERFACE_ERC721
+            || interface_id == INTERFACE_ERC721_METADATA
+    }
+}
+
+impl LancerPositions {
+
+    /// Move a position, the caller must be its owner, approved or an operator
+    fn transfer(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), PositionsError> {
+        let sender = msg_sender();
+        let owner = self.owner_of(token_id)?;
+
+        if owner != from {
+            return Err(PositionsError::Unauthorized(Unauthorized {}));
+        }
+        if to == Address::ZERO {
+            return Err(PositionsError::InvalidAddress(InvalidAddress {}));
+        }
+        if sender != owner
+            && self.token_approvals.get(token_id) != sender
+            && !self.is_approved_for_all(owner, sender)
+        {
+            return Err(PositionsError::Unauthorized(Unauthorized {}));
+        }
+
+        self.token_approvals.setter(token_id).set(Address::ZERO);
+        self.owners.setter(token_id).set(to);
+        let from_balance = self.balances.get(from);
+        self.balances.setter(from).set(from_balance - U256::from(1));
+        let to_balance = self.balances.get(to);
+        self.balances.setter(to).set(to_balance + U256::from(1));
+
+        evm::log(Transfer { from, to, token_id });
+
+        Ok(())
+    }
+}
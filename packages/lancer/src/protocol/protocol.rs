@@ -2,7 +2,9 @@
 //! Lancer Protocol Contract - Arbitrum Stylus Implementation
 //!
 //! This contract implements the dispute resolution system with judge voting
-//! and reputation management.
+//! and reputation management. Judges can stake USDC and share a weekly reward
+//! pool, funded by marketplace fees, in proportion to their coherent votes
+//! weighted by stake.
 //!
 //! Original Solidity contract converted to Rust for Arbitrum Stylus
 //! @author 0xDarioSanchez
@@ -15,13 +17,17 @@ extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
 use alloy_sol_types::sol;
+use crate::access::{AccessControl, AccessControlError, ADMIN_ROLE, ARBITRABLE_ROLE, JUDGE_MANAGER_ROLE, PAUSER_ROLE};
+use crate::metatx::{self, msg_sender};
+use crate::reputation::{self, ReputationScore};
+use crate::upgrade::{self, Migrated, UpgradeError};
 use stylus_sdk::{
-    alloy_primitives::{Address, U256, U64, U32, U8, I8, FixedBytes},
+    alloy_primitives::{Address, U256, U64, U32, U16, U8, I128, FixedBytes},
     prelude::*,
+    block,
     call::Call,
     contract,
     evm,
-    msg,
     crypto::keccak,
 };
 
@@ -32,22 +38,60 @@ use stylus_sdk::{
 sol_storage! {
     #[entrypoint]
     pub struct ProtocolContract {
-        address owner;
+        #[borrow]
+        AccessControl access_control;
         address usdc_token;
+        bool paused;
         
         uint256 contract_balance;
         uint64 dispute_count;
         uint8 number_of_votes;
-        uint256 dispute_price;
+        uint256 dispute_price;  // Flat arbitration fee, and the minimum of the proportional one
         
         mapping(address => Judge) judges;
         mapping(uint64 => Dispute) disputes;
+        
+        // Layout version this storage was initialized or migrated to.
+        // The contract runs behind a proxy: only append new fields below,
+        // and bump STORAGE_VERSION when the layout or its meaning changes.
+        uint64 storage_version;
+        
+        int128 min_judge_reputation;  // Fixed-point score judges need to register for a dispute
+        address credentials;  // Soulbound credentials minted on coherent rulings, zero to disable
+        
+        // Judge reward pool
+        mapping(uint64 => RewardEpoch) reward_epochs;  // Epoch => pool funding and coherent vote weight
+        uint256 reward_pool_reserved;  // Funded pool rewards not yet claimed
+        uint256 total_stake;  // Judge stakes (including unstaking) held by the protocol
+        
+        // Arbitration fee as a share of the disputed value, see `arbitration_cost`
+        uint16 dispute_fee_bps;  // 0 keeps the flat dispute_price
+        uint256 max_dispute_price;  // Cap on the proportional fee, 0 for no cap
+        
+        // Sum of judge balances, prizes and claimed rewards awaiting `judge_withdraw`.
+        // Balances credited before it was tracked aren't included
+        uint256 judge_balances;
+    }
+    
+    pub struct RewardEpoch {
+        uint256 rewards;  // USDC routed to the pool during the epoch
+        uint256 total_weight;  // Sum of the weights of coherent votes cast during the epoch
     }
     
     pub struct Judge {
         address judge_address;
         uint256 balance;
-        int8 reputation;
+        int8 reputation;  // Deprecated, superseded by score
+        ReputationScore score;
+        uint64 total_cases;  // Resolved disputes the judge was assigned to
+        uint64 coherent_votes;  // Votes matching the final ruling
+        uint64 non_reveals;  // Assigned disputes resolved without the judge's revealed vote
+        uint256 stake;  // USDC staked, weighs coherent votes in the reward pool
+        uint256 unstaking;  // Stake leaving the pool, withdrawable from unstake_available_at
+        uint64 unstake_available_at;
+        mapping(uint64 => uint256) epoch_weight;  // Epoch => weight of coherent votes
+        mapping(uint64 => bool) epoch_claimed;
+        uint256 pool_rewards;  // Pool rewards credited to balance so far
     }
     
     pub struct Dispute {
@@ -69,6 +113,8 @@ sol_storage! {
         bool waiting_for_judges;
         bool is_open;
         bool resolved;
+        uint256 fee;  // Arbitration fee paid for this dispute, split between its judges
+        mapping(uint256 => uint256) vote_stakes;  // Voter's stake when committing, weighs their vote in the reward pool
     }
 }
 
@@ -81,8 +127,25 @@ sol! {
     event DisputeCreated(uint256 indexed dispute_id, address indexed requester, address indexed contract_address);
     event DisputeResolved(uint256 indexed dispute_id, address winner);
     event DebugJudgeStep(uint8 step, address judge);
+    event JudgeReputationChanged(address indexed judge, uint64 indexed dispute_id, int64 points, int128 reputation);
+    event MinJudgeReputationUpdated(int128 min_reputation);
+    event CredentialsUpdated(address credentials);
+    event DisputeFeeUpdated(uint256 min_price, uint16 fee_bps, uint256 max_price);
+    event JudgeStaked(address indexed judge, uint256 amount, uint256 stake);
+    event JudgeUnstakeRequested(address indexed judge, uint256 amount, uint64 available_at);
+    event JudgeStakeWithdrawn(address indexed judge, uint256 amount);
+    event RewardPoolFunded(uint64 indexed epoch, address indexed from, uint256 amount);
+    event RewardEpochRolledOver(uint64 indexed epoch, uint64 indexed into_epoch, uint256 amount);
+    event RewardsClaimed(address indexed judge, uint64 indexed epoch, uint256 amount);
+    event Paused(address account);
+    event Unpaused(address account);
     
-    error NotOwner();
+    error Unauthorized();
+    error ContractPaused();
+    error ContractNotPaused();
+    error AlreadyInitialized();
+    error InvalidUpgrade();
+    error NothingToMigrate();
     error AlreadyRegistered();
     error NotAJudge();
     error NotTheRequester();
@@ -99,6 +162,11 @@ sol! {
     error DisputeNotResolvedYet();
     error NoBalanceToWithdraw();
     error NoUSDCToWithdraw();
+    error InsufficientStake();
+    error StakeLocked();
+    error EpochNotEnded();
+    error RewardsAlreadyClaimed();
+    error InvalidInput();
 }
 
 // ====================================
@@ -107,7 +175,12 @@ sol! {
 
 #[derive(SolidityError)]
 pub enum ProtocolError {
-    NotOwner(NotOwner),
+    Unauthorized(Unauthorized),
+    ContractPaused(ContractPaused),
+    ContractNotPaused(ContractNotPaused),
+    AlreadyInitialized(AlreadyInitialized),
+    InvalidUpgrade(InvalidUpgrade),
+    NothingToMigrate(NothingToMigrate),
     AlreadyRegistered(AlreadyRegistered),
     NotAJudge(NotAJudge),
     NotTheRequester(NotTheRequester),
@@ -124,6 +197,11 @@ pub enum ProtocolError {
     DisputeNotResolvedYet(DisputeNotResolvedYet),
     NoBalanceToWithdraw(NoBalanceToWithdraw),
     NoUSDCToWithdraw(NoUSDCToWithdraw),
+    InsufficientStake(InsufficientStake),
+    StakeLocked(StakeLocked),
+    EpochNotEnded(EpochNotEnded),
+    RewardsAlreadyClaimed(RewardsAlreadyClaimed),
+    InvalidInput(InvalidInput),
     CallFailed(CallFailed),
 }
 
@@ -138,11 +216,38 @@ impl From<stylus_sdk::call::Error> for ProtocolError {
     }
 }
 
+// Missing roles surface as Unauthorized
+impl From<AccessControlError> for ProtocolError {
+    fn from(_error: AccessControlError) -> Self {
+        ProtocolError::Unauthorized(Unauthorized {})
+    }
+}
+
+impl From<UpgradeError> for ProtocolError {
+    fn from(_error: UpgradeError) -> Self {
+        ProtocolError::InvalidUpgrade(InvalidUpgrade {})
+    }
+}
+
 // ====================================
 //        CONSTANTS          
 // ====================================
 
 const USDC_DECIMALS: u8 = 6;
+const STORAGE_VERSION: u64 = 2;
+const BPS_DENOMINATOR: u64 = 10_000;
+
+// Judge reputation points per ruling (see `reputation` for the decay model)
+const COHERENT_VOTE_POINTS: i64 = 1;
+const INCOHERENT_VOTE_POINTS: i64 = -1;
+const NON_REVEAL_POINTS: i64 = -2;
+const DEFAULT_MIN_JUDGE_REPUTATION: i128 = -3 * reputation::SCALE;
+const CREDENTIAL_COHERENT_RULING: u8 = 1; // Credential kind, see `credentials`
+
+// Judge reward pool: each coherent vote weighs the judge's stake when committing plus a floor,
+// so unstaked judges still earn; ended epochs are claimed pro-rata to weight
+const REWARD_EPOCH_DURATION: u64 = 7 * 24 * 60 * 60; // 1 week, also the unstake delay
+const VOTE_WEIGHT_FLOOR: u64 = 10 * 1_000_000; // 10 USDC
 
 // ====================================
 //      EXTERNAL INTERFACE CALLS          
@@ -150,9 +255,14 @@ const USDC_DECIMALS: u8 = 6;
 
 sol_interface! {
     interface IERC20 {
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
         function transfer(address to, uint256 amount) external returns (bool);
         function balanceOf(address account) external view returns (uint256);
     }
+    
+    interface ILancerCredentials {
+        function mint(address to, uint8 kind, uint64 reference_id, uint8 amount_bucket) external returns (uint256);
+    }
 }
 
 // ====================================
@@ -160,6 +270,7 @@ sol_interface! {
 // ====================================
 
 #[public]
+#[inherit(AccessControl)]
 impl ProtocolContract {
     
     // ====================================
@@ -167,16 +278,27 @@ impl ProtocolContract {
     // ====================================
     
     /// Initialize the protocol contract
+    /// `owner` receives the admin, judge manager and pauser roles;
+    /// marketplaces must be granted ARBITRABLE_ROLE before opening disputes
     pub fn init(
         &mut self,
         owner: Address,
         usdc: Address,
     ) -> Result<(), ProtocolError> {
-        self.owner.set(owner);
+        // Can only be initialized once (directly or through the proxy)
+        if self.storage_version.get() != U64::ZERO {
+            return Err(ProtocolError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        
+        self.storage_version.set(U64::from(STORAGE_VERSION));
+        self.access_control._grant_role(ADMIN_ROLE, owner);
+        self.access_control._grant_role(JUDGE_MANAGER_ROLE, owner);
+        self.access_control._grant_role(PAUSER_ROLE, owner);
         self.usdc_token.set(usdc);
         self.contract_balance.set(U256::ZERO);
         self.dispute_count.set(U64::from(1));
         self.number_of_votes.set(U8::from(5));
+        self.min_judge_reputation.set(I128::from_le_bytes(DEFAULT_MIN_JUDGE_REPUTATION.to_le_bytes()));
         
         // 50 USDC with 6 decimals
         let dispute_price = U256::from(50u64) * U256::from(10u64.pow(USDC_DECIMALS as u32));
@@ -186,14 +308,12 @@ impl ProtocolContract {
     }
     
     // ====================================
-    //        ONLY-OWNER FUNCTIONS          
+    //        ROLE-GATED FUNCTIONS          
     // ====================================
     
-    /// Update the number of votes required to resolve a dispute
+    /// Update the number of votes required to resolve a dispute (only judge manager)
     pub fn update_number_of_votes(&mut self, new_number: u8) -> Result<(), ProtocolError> {
-        if msg::sender() != self.owner.get() {
-            return Err(ProtocolError::NotOwner(NotOwner {}));
-        }
+        self.access_control.only_role(JUDGE_MANAGER_ROLE, msg_sender())?;
         
         if new_number == 0 {
             return Err(ProtocolError::MustBeGreaterThanZero(MustBeGreaterThanZero {}));
@@ -203,12 +323,45 @@ impl ProtocolContract {
         Ok(())
     }
     
-    /// Withdraw available USDC (excludes judge rewards)
-    pub fn withdraw(&mut self) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
-        if sender != self.owner.get() {
-            return Err(ProtocolError::NotOwner(NotOwner {}));
+    /// Update the fixed-point reputation judges need to register for a dispute (only judge manager)
+    pub fn set_min_judge_reputation(&mut self, min_reputation: i128) -> Result<(), ProtocolError> {
+        self.access_control.only_role(JUDGE_MANAGER_ROLE, msg_sender())?;
+        
+        self.min_judge_reputation.set(I128::from_le_bytes(min_reputation.to_le_bytes()));
+        evm::log(MinJudgeReputationUpdated { min_reputation });
+        
+        Ok(())
+    }
+    
+    /// Update the arbitration fee (only admin)
+    /// The fee is `fee_bps` of the disputed value, bounded by `min_price` and `max_price`
+    /// (0 for no cap); `fee_bps` 0 charges a flat `min_price`
+    pub fn set_dispute_fee(&mut self, min_price: U256, fee_bps: u16, max_price: U256) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        if min_price == U256::ZERO {
+            return Err(ProtocolError::MustBeGreaterThanZero(MustBeGreaterThanZero {}));
         }
+        if u64::from(fee_bps) > BPS_DENOMINATOR || (max_price != U256::ZERO && max_price < min_price) {
+            return Err(ProtocolError::InvalidInput(InvalidInput {}));
+        }
+        
+        self.dispute_price.set(min_price);
+        self.dispute_fee_bps.set(U16::from(fee_bps));
+        self.max_dispute_price.set(max_price);
+        evm::log(DisputeFeeUpdated {
+            min_price,
+            fee_bps,
+            max_price,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw available USDC (excludes judge balances, the reward pool and stakes, only admin)
+    pub fn withdraw(&mut self) -> Result<(), ProtocolError> {
+        let sender = msg_sender();
+        self.access_control.only_role(ADMIN_ROLE, sender)?;
         
         let usdc = self.usdc_token.get();
         let token = IERC20::new(usdc);
@@ -216,12 +369,16 @@ impl ProtocolContract {
         let balance = token.balance_of(call, contract::address())?;
         
         let contract_balance = self.contract_balance.get();
+        let reserved = contract_balance
+            + self.reward_pool_reserved.get()
+            + self.total_stake.get()
+            + self.judge_balances.get();
         
-        if balance <= contract_balance {
+        if balance <= reserved {
             return Err(ProtocolError::NoUSDCToWithdraw(NoUSDCToWithdraw {}));
         }
         
-        let amount_to_withdraw = balance - contract_balance;
+        let amount_to_withdraw = balance - reserved;
         
         // Reset contract balance
         self.contract_balance.set(U256::ZERO);
@@ -238,43 +395,182 @@ impl ProtocolContract {
         Ok(())
     }
     
+    /// Set the credentials collection judges receive on coherent rulings, zero to disable (only admin)
+    pub fn set_credentials(&mut self, credentials: Address) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        self.credentials.set(credentials);
+        evm::log(CredentialsUpdated { credentials });
+        
+        Ok(())
+    }
+    
+    /// Get the credentials collection
+    pub fn credentials(&self) -> Address {
+        self.credentials.get()
+    }
+    
+    /// Stop new disputes and judge assignments (only pauser)
+    pub fn pause(&mut self) -> Result<(), ProtocolError> {
+        self.access_control.only_role(PAUSER_ROLE, msg_sender())?;
+        
+        if self.paused.get() {
+            return Err(ProtocolError::ContractPaused(ContractPaused {}));
+        }
+        
+        self.paused.set(true);
+        evm::log(Paused { account: msg_sender() });
+        
+        Ok(())
+    }
+    
+    /// Resume normal operation (only pauser)
+    pub fn unpause(&mut self) -> Result<(), ProtocolError> {
+        self.access_control.only_role(PAUSER_ROLE, msg_sender())?;
+        
+        if !self.paused.get() {
+            return Err(ProtocolError::ContractNotPaused(ContractNotPaused {}));
+        }
+        
+        self.paused.set(false);
+        evm::log(Unpaused { account: msg_sender() });
+        
+        Ok(())
+    }
+    
+    // ====================================
+    //            UPGRADES          
+    // ====================================
+    
+    /// Switch the proxy to a new implementation (only admin, through the proxy)
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), ProtocolError> {
+        self.authorize_upgrade(new_implementation)?;
+        
+        let current_version = u64::from_le_bytes(self.storage_version.get().to_le_bytes());
+        upgrade::upgrade_to(self, current_version, new_implementation)?;
+        
+        Ok(())
+    }
+    
+    /// Bring storage up to STORAGE_VERSION after an upgrade (only admin)
+    pub fn migrate(&mut self) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        let from_version = u64::from_le_bytes(self.storage_version.get().to_le_bytes());
+        if from_version == 0 || from_version >= STORAGE_VERSION {
+            return Err(ProtocolError::NothingToMigrate(NothingToMigrate {}));
+        }
+        
+        // Migration steps are applied in order, one block per version bump
+        
+        // v2: decaying judge scores. The registration threshold starts at its default;
+        // judges can't be enumerated, so v1 int8 reputations restart from a neutral score
+        if from_version < 2 {
+            self.min_judge_reputation.set(I128::from_le_bytes(DEFAULT_MIN_JUDGE_REPUTATION.to_le_bytes()));
+        }
+        
+        self.storage_version.set(U64::from(STORAGE_VERSION));
+        evm::log(Migrated {
+            from_version,
+            to_version: STORAGE_VERSION,
+        });
+        
+        Ok(())
+    }
+    
+    /// ERC-1822 proxiable UUID (the EIP-1967 implementation slot)
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> FixedBytes<32> {
+        upgrade::proxiable_uuid()
+    }
+    
+    /// Storage layout version implemented by this code
+    pub fn layout_version() -> u64 {
+        STORAGE_VERSION
+    }
+    
+    /// Storage layout version recorded in storage
+    pub fn storage_version(&self) -> u64 {
+        u64::from_le_bytes(self.storage_version.get().to_le_bytes())
+    }
+    
+    /// Current implementation behind the proxy (zero when called directly)
+    pub fn implementation(&self) -> Address {
+        upgrade::implementation()
+    }
+    
+    // ====================================
+    //         META-TRANSACTIONS
+    // ====================================
+    
+    /// Set the ERC-2771 trusted forwarder, the zero address disables it (only admin)
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        
+        metatx::set_trusted_forwarder(forwarder);
+        Ok(())
+    }
+    
+    /// Get the trusted forwarder
+    pub fn trusted_forwarder(&self) -> Address {
+        metatx::trusted_forwarder()
+    }
+    
+    /// Whether calls from `forwarder` carry a trusted sender (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        metatx::is_trusted_forwarder(forwarder)
+    }
+    
     // ====================================
     //         EXTERNAL FUNCTIONS          
     // ====================================
     
     /// Register as a judge
     pub fn register_as_judge(&mut self) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
-        let judge = self.judges.get(sender);
+        let sender = msg_sender();
         
-        if judge.judge_address.get() != Address::ZERO {
+        if self.is_judge(sender) {
             return Err(ProtocolError::AlreadyRegistered(AlreadyRegistered {}));
         }
         
-        let mut new_judge = self.judges.setter(sender);
-        new_judge.judge_address.set(sender);
-        new_judge.balance.set(U256::ZERO);
-        new_judge.reputation.set(I8::ZERO);
-        
-        evm::log(JudgeRegistered { judge: sender });
+        self.register_judge(sender);
+        Ok(())
+    }
+    
+    /// Register a judge on their behalf (called by Marketplace contract, only arbitrable)
+    /// Already registered judges are left as they are
+    pub fn register_judge_for(&mut self, judge: Address) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ARBITRABLE_ROLE, msg_sender())?;
         
+        if !self.is_judge(judge) {
+            self.register_judge(judge);
+        }
         Ok(())
     }
     
-    /// Create a dispute (called by Marketplace contract)
+    /// Create a dispute (called by Marketplace contract, only arbitrable)
+    /// `fee` is the arbitration fee the caller transferred for it (see `arbitration_cost`)
+    /// Returns the dispute id
     pub fn create_dispute(
         &mut self,
         deal_id: u64,
         requester: Address,
         _proof: String,
-    ) -> Result<(), ProtocolError> {
+        fee: U256,
+    ) -> Result<u64, ProtocolError> {
+        self.access_control.only_role(ARBITRABLE_ROLE, msg_sender())?;
+        
+        if self.paused.get() {
+            return Err(ProtocolError::ContractPaused(ContractPaused {}));
+        }
+        
         let dispute_id = self.dispute_count.get();
         let dispute_id_u64 = u64::from_le_bytes(dispute_id.to_le_bytes());
         
         let mut dispute = self.disputes.setter(dispute_id);
         dispute.dispute_id.set(U32::from(deal_id));
         dispute.requester.set(requester);
-        dispute.contract_address.set(msg::sender());
+        dispute.contract_address.set(msg_sender());
         dispute.waiting_for_judges.set(true);
         dispute.is_open.set(false);
         dispute.resolved.set(false);
@@ -282,18 +578,19 @@ impl ProtocolContract {
         dispute.votes_against.set(U8::ZERO);
         dispute.able_to_vote_count.set(U256::ZERO);
         dispute.voters_count.set(U256::ZERO);
+        dispute.fee.set(fee);
         
         evm::log(DisputeCreated {
             dispute_id: U256::from(dispute_id_u64),
             requester,
-            contract_address: msg::sender(),
+            contract_address: msg_sender(),
         });
         
         // Increment counter
         let current_counter = self.dispute_count.get();
         self.dispute_count.set(current_counter + U64::from(1));
         
-        Ok(())
+        Ok(dispute_id_u64)
     }
     
     /// Update dispute proofs for payer
@@ -303,6 +600,7 @@ impl ProtocolContract {
         requester: Address,
         _proof: String,
     ) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ARBITRABLE_ROLE, msg_sender())?;
         let dispute = self.disputes.get(U64::from(dispute_id));
         
         if dispute.requester.get() != requester {
@@ -330,6 +628,7 @@ impl ProtocolContract {
         beneficiary: Address,
         _proof: String,
     ) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ARBITRABLE_ROLE, msg_sender())?;
         let dispute = self.disputes.get(U64::from(dispute_id));
         
         if dispute.beneficiary.get() != beneficiary {
@@ -351,7 +650,12 @@ impl ProtocolContract {
     
     /// Register to vote on a dispute
     pub fn register_to_vote(&mut self, dispute_id: u64) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
+        
+        if self.paused.get() {
+            return Err(ProtocolError::ContractPaused(ContractPaused {}));
+        }
+        
         evm::log(DebugJudgeStep { step: 1, judge: sender });
         let judge = self.judges.get(sender);
         
@@ -360,8 +664,7 @@ impl ProtocolContract {
             return Err(ProtocolError::NotAJudge(NotAJudge {}));
         }
         
-        let reputation = i8::from_le_bytes(judge.reputation.get().to_le_bytes());
-        if reputation < -3 {
+        if judge.score.current() < self.min_judge_reputation() {
             evm::log(DebugJudgeStep { step: 3, judge: sender });
             return Err(ProtocolError::NotEnoughReputation(NotEnoughReputation {}));
         }
@@ -413,7 +716,7 @@ impl ProtocolContract {
     
     // /// Vote on a dispute
     // pub fn vote(&mut self, dispute_id: u64, support: bool) -> Result<(), ProtocolError> {
-    //     let sender = msg::sender();
+    //     let sender = msg_sender();
     //     let dispute = self.disputes.get(U64::from(dispute_id));
         
     //     if dispute.resolved.get() {
@@ -548,7 +851,7 @@ impl ProtocolContract {
     
 
     pub fn commit_vote(&mut self, dispute_id: u64, commit_hash: [u8; 32]) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let mut dispute = self.disputes.setter(U64::from(dispute_id));
 
         if dispute.resolved.get() {
@@ -580,9 +883,11 @@ impl ProtocolContract {
             }
         }
 
-        // Store commit
+        // Store commit, with the stake the vote will weigh in the reward pool
+        let stake = self.judges.get(sender).stake.get();
         dispute.voters.setter(commits).set(sender);
         dispute.vote_commits.setter(commits).set(FixedBytes::from(commit_hash));
+        dispute.vote_stakes.setter(commits).set(stake);
         dispute.commits_count.set(commits + U256::from(1u64));
 
         Ok(())
@@ -596,7 +901,7 @@ impl ProtocolContract {
         vote: bool,
         secret: Vec<u8>
     ) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let mut dispute = self.disputes.setter(U64::from(dispute_id));
 
         if dispute.resolved.get() {
@@ -669,68 +974,91 @@ impl ProtocolContract {
 
             let votes_for = u8::from_le_bytes(dispute.votes_for.get().to_le_bytes());
             let votes_against = u8::from_le_bytes(dispute.votes_against.get().to_le_bytes());
-            let prize = self.dispute_price.get() / U256::from(required_votes_u64);
+            // Disputes opened before fees were recorded paid the flat price
+            let fee = match dispute.fee.get() {
+                fee if fee == U256::ZERO => self.dispute_price.get(),
+                fee => fee,
+            };
+            let prize = fee / U256::from(required_votes_u64);
+            let epoch = current_epoch();
+            let mut epoch_weight = U256::ZERO;
 
             let requester = dispute.requester.get();
             let beneficiary = dispute.beneficiary.get();
+            let requester_wins = votes_for > votes_against;
 
-            if votes_for > votes_against {
-                // Requester wins
-                for i in 0..required_votes_u64 {
-                    let voter = dispute.voters.get(U256::from(i));
-                    let vote_val = dispute.vote_plain.get(U256::from(i));
-                    let mut judge = self.judges.setter(voter);
-                    let rep = judge.reputation.get();
-
-                    if vote_val {
-                        // Voted for winner (requester)
-                        judge.reputation.set(rep + I8::from_le_bytes([1, 0, 0, 0, 0, 0, 0, 0]));
-                        let bal = judge.balance.get();
-                        judge.balance.set(bal + prize);
-                    } else {
-                        // Voted for loser
-                        judge.reputation.set(rep - I8::from_le_bytes([1, 0, 0, 0, 0, 0, 0, 0]));
+            // Pay and score every judge assigned to the dispute
+            let able_count = dispute.able_to_vote_count.get().as_limbs()[0];
+            let mut coherent_judges = Vec::new();
+            for i in 0..able_count {
+                let judge_addr = dispute.able_to_vote.get(U256::from(i));
+                let mut vote = None;
+                let mut vote_stake = U256::ZERO;
+                for j in 0..commit_count.as_limbs()[0] {
+                    if dispute.voters.get(U256::from(j)) == judge_addr && dispute.revealed.get(U256::from(j)) {
+                        vote = Some(dispute.vote_plain.get(U256::from(j)));
+                        vote_stake = dispute.vote_stakes.get(U256::from(j));
+                        break;
                     }
                 }
 
-                // Contract keeps losing votes' prizes
-                let contract_reward = prize * U256::from(votes_against as u64);
-                let current_contract_balance = self.contract_balance.get();
-                self.contract_balance.set(current_contract_balance + contract_reward);
+                let mut judge = self.judges.setter(judge_addr);
+                let total_cases = judge.total_cases.get();
+                judge.total_cases.set(total_cases + U64::from(1));
 
-                evm::log(DisputeResolved {
-                    dispute_id: U256::from(dispute_id),
-                    winner: requester,
-                });
-            } else {
-                // Beneficiary wins
-                for i in 0..required_votes_u64 {
-                    let voter = dispute.voters.get(U256::from(i));
-                    let vote_val = dispute.vote_plain.get(U256::from(i));
-                    let mut judge = self.judges.setter(voter);
-                    let rep = judge.reputation.get();
-
-                    if !vote_val {
-                        // Voted for winner (beneficiary)
-                        judge.reputation.set(rep + I8::from_le_bytes([1, 0, 0, 0, 0, 0, 0, 0]));
+                let points = match vote {
+                    // Voted for the winner
+                    Some(vote_val) if vote_val == requester_wins => {
                         let bal = judge.balance.get();
                         judge.balance.set(bal + prize);
-                    } else {
-                        // Voted for loser
-                        judge.reputation.set(rep - I8::from_le_bytes([1, 0, 0, 0, 0, 0, 0, 0]));
+                        let coherent_votes = judge.coherent_votes.get();
+                        judge.coherent_votes.set(coherent_votes + U64::from(1));
+                        let weight = vote_stake + U256::from(VOTE_WEIGHT_FLOOR);
+                        let judge_weight = judge.epoch_weight.get(U64::from(epoch));
+                        judge.epoch_weight.setter(U64::from(epoch)).set(judge_weight + weight);
+                        epoch_weight += weight;
+                        coherent_judges.push(judge_addr);
+                        COHERENT_VOTE_POINTS
                     }
-                }
-
-                // Contract keeps losing votes' prizes
-                let contract_reward = prize * U256::from(votes_for as u64);
-                let current_contract_balance = self.contract_balance.get();
-                self.contract_balance.set(current_contract_balance + contract_reward);
+                    // Voted for the loser
+                    Some(_) => INCOHERENT_VOTE_POINTS,
+                    None => {
+                        let non_reveals = judge.non_reveals.get();
+                        judge.non_reveals.set(non_reveals + U64::from(1));
+                        NON_REVEAL_POINTS
+                    }
+                };
+                let reputation = judge.score.add_points(points);
 
-                evm::log(DisputeResolved {
-                    dispute_id: U256::from(dispute_id),
-                    winner: beneficiary,
+                evm::log(JudgeReputationChanged {
+                    judge: judge_addr,
+                    dispute_id,
+                    points,
+                    reputation,
                 });
             }
+
+            let mut reward_epoch = self.reward_epochs.setter(U64::from(epoch));
+            let total_weight = reward_epoch.total_weight.get();
+            reward_epoch.total_weight.set(total_weight + epoch_weight);
+            
+            let judge_balances = self.judge_balances.get();
+            self.judge_balances.set(judge_balances + prize * U256::from(coherent_judges.len()));
+            
+            // Contract keeps the prizes of losing and missing votes
+            let winning_votes = if requester_wins { votes_for } else { votes_against };
+            let contract_reward = prize * U256::from(required_votes_u64 - winning_votes as u64);
+            let current_contract_balance = self.contract_balance.get();
+            self.contract_balance.set(current_contract_balance + contract_reward);
+
+            evm::log(DisputeResolved {
+                dispute_id: U256::from(dispute_id),
+                winner: if requester_wins { requester } else { beneficiary },
+            });
+
+            for judge_addr in coherent_judges {
+                self.issue_credential(judge_addr, dispute_id);
+            }
         }
 
         Ok(())
@@ -760,11 +1088,11 @@ impl ProtocolContract {
         &self,
         dispute_id: u64,
         judge_addr: Address,
-    ) -> (bool, I8, bool, bool, bool, bool, U256) {
+    ) -> (bool, i128, bool, bool, bool, bool, U256) {
         let judge = self.judges.get(judge_addr);
         let is_registered_judge = judge.judge_address.get() != Address::ZERO;
-        let reputation = judge.reputation.get();
-        let enough_reputation = i8::from_le_bytes(reputation.to_le_bytes()) >= -3;
+        let reputation = judge.score.current();
+        let enough_reputation = reputation >= self.min_judge_reputation();
 
         let dispute_key = U64::from(dispute_id);
         let dispute = self.disputes.get(dispute_key);
@@ -803,7 +1131,7 @@ impl ProtocolContract {
     
     /// Judge withdraw their balance
     pub fn judge_withdraw(&mut self) -> Result<(), ProtocolError> {
-        let sender = msg::sender();
+        let sender = msg_sender();
         let judge = self.judges.get(sender);
         
         if judge.judge_address.get() == Address::ZERO {
@@ -818,6 +1146,8 @@ impl ProtocolContract {
         // Reset balance
         let mut judge_mut = self.judges.setter(sender);
         judge_mut.balance.set(U256::ZERO);
+        let judge_balances = self.judge_balances.get();
+        self.judge_balances.set(judge_balances.saturating_sub(balance));
         
         // Transfer USDC
         let usdc = self.usdc_token.get();
@@ -833,12 +1163,201 @@ impl ProtocolContract {
     }
     
     // ====================================
-    //        VIEW FUNCTIONS          
+    //        JUDGE REWARD POOL
     // ====================================
     
-    /// Get owner address
-    pub fn owner(&self) -> Address {
-        self.owner.get()
+    /// Stake USDC to weigh coherent votes in the reward pool (only judges)
+    /// Judge must approve the protocol to spend `amount` first
+    pub fn stake(&mut self, amount: U256) -> Result<(), ProtocolError> {
+        let sender = msg_sender();
+        
+        if !self.is_judge(sender) {
+            return Err(ProtocolError::NotAJudge(NotAJudge {}));
+        }
+        if amount == U256::ZERO {
+            return Err(ProtocolError::MustBeGreaterThanZero(MustBeGreaterThanZero {}));
+        }
+        
+        let token = IERC20::new(self.usdc_token.get());
+        let call = Call::new_in(self);
+        let success = token.transfer_from(call, sender, contract::address(), amount)?;
+        if !success {
+            return Err(ProtocolError::CallFailed(CallFailed {}));
+        }
+        
+        let mut judge = self.judges.setter(sender);
+        let stake = judge.stake.get() + amount;
+        judge.stake.set(stake);
+        let total_stake = self.total_stake.get();
+        self.total_stake.set(total_stake + amount);
+        
+        evm::log(JudgeStaked {
+            judge: sender,
+            amount,
+            stake,
+        });
+        
+        Ok(())
+    }
+    
+    /// Stop `amount` of stake from weighing votes, withdrawable after one epoch
+    /// Requesting again adds to the pending amount and restarts the delay
+    pub fn request_unstake(&mut self, amount: U256) -> Result<(), ProtocolError> {
+        let sender = msg_sender();
+        let mut judge = self.judges.setter(sender);
+        
+        let stake = judge.stake.get();
+        if amount == U256::ZERO || amount > stake {
+            return Err(ProtocolError::InsufficientStake(InsufficientStake {}));
+        }
+        
+        let available_at = block::timestamp() + REWARD_EPOCH_DURATION;
+        judge.stake.set(stake - amount);
+        let unstaking = judge.unstaking.get();
+        judge.unstaking.set(unstaking + amount);
+        judge.unstake_available_at.set(U64::from(available_at));
+        
+        evm::log(JudgeUnstakeRequested {
+            judge: sender,
+            amount,
+            available_at,
+        });
+        
+        Ok(())
+    }
+    
+    /// Withdraw stake whose unstake delay has passed
+    pub fn withdraw_stake(&mut self) -> Result<(), ProtocolError> {
+        let sender = msg_sender();
+        let mut judge = self.judges.setter(sender);
+        
+        let amount = judge.unstaking.get();
+        if amount == U256::ZERO {
+            return Err(ProtocolError::NoBalanceToWithdraw(NoBalanceToWithdraw {}));
+        }
+        if block::timestamp() < u64::from_le_bytes(judge.unstake_available_at.get().to_le_bytes()) {
+            return Err(ProtocolError::StakeLocked(StakeLocked {}));
+        }
+        
+        judge.unstaking.set(U256::ZERO);
+        let total_stake = self.total_stake.get();
+        self.total_stake.set(total_stake - amount);
+        
+        let token = IERC20::new(self.usdc_token.get());
+        let call = Call::new_in(self);
+        let success = token.transfer(call, sender, amount)?;
+        if !success {
+            return Err(ProtocolError::CallFailed(CallFailed {}));
+        }
+        
+        evm::log(JudgeStakeWithdrawn {
+            judge: sender,
+            amount,
+        });
+        
+        Ok(())
+    }
+    
+    /// Pull `amount` USDC from the caller into the current epoch's pool (only arbitrable)
+    /// The caller must approve the protocol to spend it first
+    pub fn fund_reward_pool(&mut self, amount: U256) -> Result<(), ProtocolError> {
+        let sender = msg_sender();
+        self.access_control.only_role(ARBITRABLE_ROLE, sender)?;
+        
+        let token = IERC20::new(self.usdc_token.get());
+        let call = Call::new_in(self);
+        let success = token.transfer_from(call, sender, contract::address(), amount)?;
+        if !success {
+            return Err(ProtocolError::CallFailed(CallFailed {}));
+        }
+
+        let epoch = current_epoch();
+        let mut reward_epoch = self.reward_epochs.setter(U64::from(epoch));
+        let rewards = reward_epoch.rewards.get();
+        reward_epoch.rewards.set(rewards + amount);
+        let reserved = self.reward_pool_reserved.get();
+        self.reward_pool_reserved.set(reserved + amount);
+        
+        evm::log(RewardPoolFunded {
+            epoch,
+            from: sender,
+            amount,
+        });
+        
+        Ok(())
+    }
+    
+    /// Move the rewards of an ended epoch without coherent votes into the current epoch
+    pub fn roll_over_epoch(&mut self, epoch: u64) -> Result<(), ProtocolError> {
+        let current = current_epoch();
+        if epoch >= current {
+            return Err(ProtocolError::EpochNotEnded(EpochNotEnded {}));
+        }
+        
+        let mut ended = self.reward_epochs.setter(U64::from(epoch));
+        let amount = ended.rewards.get();
+        if ended.total_weight.get() != U256::ZERO || amount == U256::ZERO {
+            return Err(ProtocolError::NoBalanceToWithdraw(NoBalanceToWithdraw {}));
+        }
+        ended.rewards.set(U256::ZERO);
+        
+        let mut reward_epoch = self.reward_epochs.setter(U64::from(current));
+        let rewards = reward_epoch.rewards.get();
+        reward_epoch.rewards.set(rewards + amount);
+        
+        evm::log(RewardEpochRolledOver {
+            epoch,
+            into_epoch: current,
+            amount,
+        });
+        
+        Ok(())
+    }
+    
+    /// Credit a judge's share of an ended epoch's pool to their balance
+    /// Returns the amount credited, withdrawable through `judge_withdraw`
+    pub fn claim_rewards(&mut self, epoch: u64) -> Result<U256, ProtocolError> {
+        let sender = msg_sender();
+        
+        if epoch >= current_epoch() {
+            return Err(ProtocolError::EpochNotEnded(EpochNotEnded {}));
+        }
+        
+        let amount = self.epoch_rewards_of(sender, epoch);
+        let mut judge = self.judges.setter(sender);
+        if judge.epoch_claimed.get(U64::from(epoch)) {
+            return Err(ProtocolError::RewardsAlreadyClaimed(RewardsAlreadyClaimed {}));
+        }
+        if amount == U256::ZERO {
+            return Err(ProtocolError::NoBalanceToWithdraw(NoBalanceToWithdraw {}));
+        }
+        
+        judge.epoch_claimed.setter(U64::from(epoch)).set(true);
+        let balance = judge.balance.get();
+        judge.balance.set(balance + amount);
+        let pool_rewards = judge.pool_rewards.get();
+        judge.pool_rewards.set(pool_rewards + amount);
+        let reserved = self.reward_pool_reserved.get();
+        self.reward_pool_reserved.set(reserved - amount);
+        let judge_balances = self.judge_balances.get();
+        self.judge_balances.set(judge_balances + amount);
+        
+        evm::log(RewardsClaimed {
+            judge: sender,
+            epoch,
+            amount,
+        });
+        
+        Ok(amount)
+    }
+    
+    // ====================================
+    //        VIEW FUNCTIONS
+    // ====================================
+    
+    /// Check if the protocol is paused
+    pub fn paused(&self) -> bool {
+        self.paused.get()
     }
     
     /// Get dispute count
@@ -851,24 +1370,100 @@ impl ProtocolContract {
         u8::from_le_bytes(self.number_of_votes.get().to_le_bytes())
     }
     
-    /// Get dispute price
+    /// Get dispute price (the minimum arbitration fee)
     pub fn dispute_price(&self) -> U256 {
         self.dispute_price.get()
     }
     
+    /// Get the arbitration fee configuration
+    /// Returns (min_price, fee_bps, max_price)
+    pub fn get_dispute_fee(&self) -> (U256, u16, U256) {
+        (
+            self.dispute_price.get(),
+            u16::from_le_bytes(self.dispute_fee_bps.get().to_le_bytes()),
+            self.max_dispute_price.get(),
+        )
+    }
+    
+    /// Get the arbitration fee for a dispute over `disputed_value` USDC
+    /// Arbitrables convert values in other tokens to USDC before asking
+    pub fn arbitration_cost(&self, disputed_value: U256) -> U256 {
+        arbitration_fee(
+            disputed_value,
+            u16::from_le_bytes(self.dispute_fee_bps.get().to_le_bytes()),
+            self.dispute_price.get(),
+            self.max_dispute_price.get(),
+        )
+    }
+    
     /// Check if dispute is resolved
     pub fn check_if_dispute_is_resolved(&self, dispute_id: u64) -> bool {
         let dispute = self.disputes.get(U64::from(dispute_id));
         dispute.resolved.get()
     }
     
+    /// Get fixed-point reputation judges need to register for a dispute
+    pub fn min_judge_reputation(&self) -> i128 {
+        i128::from_le_bytes(self.min_judge_reputation.get().to_le_bytes())
+    }
+    
+    /// Get the current reward epoch
+    #[selector(name = "currentEpoch")]
+    pub fn reward_epoch(&self) -> u64 {
+        current_epoch()
+    }
+    
+    /// Get a reward epoch
+    /// Returns (rewards, total_weight)
+    pub fn get_reward_epoch(&self, epoch: u64) -> (U256, U256) {
+        let reward_epoch = self.reward_epochs.get(U64::from(epoch));
+        (reward_epoch.rewards.get(), reward_epoch.total_weight.get())
+    }
+    
+    /// Get USDC reserved for unclaimed pool rewards
+    pub fn reward_pool_reserved(&self) -> U256 {
+        self.reward_pool_reserved.get()
+    }
+    
+    /// Get a judge's stake
+    /// Returns (stake, unstaking, unstake_available_at, pool_rewards claimed so far)
+    pub fn get_judge_stake(&self, judge_address: Address) -> (U256, U256, u64, U256) {
+        let judge = self.judges.get(judge_address);
+        (
+            judge.stake.get(),
+            judge.unstaking.get(),
+            u64::from_le_bytes(judge.unstake_available_at.get().to_le_bytes()),
+            judge.pool_rewards.get(),
+        )
+    }
+    
+    /// Get a judge's share of an epoch's pool
+    /// Returns (weight, rewards, claimed); rewards of the current epoch are an estimate
+    pub fn get_judge_epoch_rewards(&self, judge_address: Address, epoch: u64) -> (U256, U256, bool) {
+        let judge = self.judges.get(judge_address);
+        (
+            judge.epoch_weight.get(U64::from(epoch)),
+            self.epoch_rewards_of(judge_address, epoch),
+            judge.epoch_claimed.get(U64::from(epoch)),
+        )
+    }
+    
+    /// Check if an address is a registered judge
+    pub fn is_judge(&self, judge_address: Address) -> bool {
+        self.judges.get(judge_address).judge_address.get() != Address::ZERO
+    }
+    
     /// Get judge info
-    pub fn get_judge(&self, judge_address: Address) -> (Address, U256, i8) {
+    /// Returns (address, balance, decayed fixed-point reputation, total_cases, coherent_votes, non_reveals)
+    pub fn get_judge(&self, judge_address: Address) -> (Address, U256, i128, u64, u64, u64) {
         let judge = self.judges.get(judge_address);
         (
             judge.judge_address.get(),
             judge.balance.get(),
-            i8::from_le_bytes(judge.reputation.get().to_le_bytes()),
+            judge.score.current(),
+            u64::from_le_bytes(judge.total_cases.get().to_le_bytes()),
+            u64::from_le_bytes(judge.coherent_votes.get().to_le_bytes()),
+            u64::from_le_bytes(judge.non_reveals.get().to_le_bytes()),
         )
     }
     
@@ -894,4 +1489,96 @@ impl ProtocolContract {
             u8::from_le_bytes(dispute.votes_against.get().to_le_bytes()),
         )
     }
-}
\ No newline at end of file
+}
+
+impl ProtocolContract {
+    
+    /// A judge's pro-rata share of an epoch's pool
+    fn epoch_rewards_of(&self, judge: Address, epoch: u64) -> U256 {
+        let reward_epoch = self.reward_epochs.get(U64::from(epoch));
+        let total_weight = reward_epoch.total_weight.get();
+        if total_weight == U256::ZERO {
+            return U256::ZERO;
+        }
+        
+        let weight = self.judges.get(judge).epoch_weight.get(U64::from(epoch));
+        reward_epoch.rewards.get() * weight / total_weight
+    }
+    
+    /// Mint a coherent ruling credential to a judge
+    /// Failures are ignored so a misconfigured collection never blocks a ruling
+    fn issue_credential(&mut self, judge: Address, dispute_id: u64) {
+        let credentials = self.credentials.get();
+        if credentials == Address::ZERO {
+            return;
+        }
+        
+        let collection = ILancerCredentials::new(credentials);
+        let call = Call::new_in(self);
+        let _ = collection.mint(call, judge, CREDENTIAL_COHERENT_RULING, dispute_id, 0);
+    }
+    
+    /// Store a new judge with a neutral reputation
+    fn register_judge(&mut self, judge: Address) {
+        let mut new_judge = self.judges.setter(judge);
+        new_judge.judge_address.set(judge);
+        new_judge.balance.set(U256::ZERO);
+        
+        evm::log(JudgeRegistered { judge });
+    }
+    
+    /// Upgrade authorization hook
+    fn authorize_upgrade(&self, _new_implementation: Address) -> Result<(), ProtocolError> {
+        self.access_control.only_role(ADMIN_ROLE, msg_sender())?;
+        Ok(())
+    }
+}
+/// Reward epoch of the current block
+fn current_epoch() -> u64 {
+    block::timestamp() / REWARD_EPOCH_DURATION
+}
+
+/// Arbitration fee: `fee_bps` of the disputed value, at least `min_price` and at most
+/// `max_price` (0 for no cap)
+fn arbitration_fee(disputed_value: U256, fee_bps: u16, min_price: U256, max_price: U256) -> U256 {
+    let proportional = disputed_value * U256::from(fee_bps) / U256::from(BPS_DENOMINATOR);
+
+    let fee = proportional.max(min_price);
+    if max_price != U256::ZERO {
+        fee.min(max_price)
+    } else {
+        fee
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc(amount: u64) -> U256 {
+        U256::from(amount) * U256::from(10u64.pow(USDC_DECIMALS as u32))
+    }
+
+    #[test]
+    fn arbitration_fee_is_proportional_between_bounds() {
+        // 2% of 5,000 USDC
+        assert_eq!(arbitration_fee(usdc(5_000), 200, usdc(50), usdc(500)), usdc(100));
+    }
+
+    #[test]
+    fn arbitration_fee_never_drops_below_the_minimum() {
+        assert_eq!(arbitration_fee(usdc(100), 200, usdc(50), usdc(500)), usdc(50));
+        assert_eq!(arbitration_fee(U256::ZERO, 200, usdc(50), usdc(500)), usdc(50));
+    }
+
+    #[test]
+    fn arbitration_fee_is_capped_at_the_maximum() {
+        assert_eq!(arbitration_fee(usdc(1_000_000), 200, usdc(50), usdc(500)), usdc(500));
+    }
+
+    #[test]
+    fn arbitration_fee_zero_settings_disable_cap_and_share() {
+        assert_eq!(arbitration_fee(usdc(1_000_000), 200, usdc(50), U256::ZERO), usdc(20_000));
+        assert_eq!(arbitration_fee(usdc(1_000_000), 0, usdc(50), U256::ZERO), usdc(50));
+    }
+}
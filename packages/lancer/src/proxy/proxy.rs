@@ -0,0 +1,101 @@
+//!
+//! Lancer Proxy - Arbitrum Stylus Implementation
+//!
+//! Minimal ERC-1967 proxy used to deploy the Marketplace and Protocol contracts
+//! behind a stable address. Every call is delegated to the implementation stored
+//! in the EIP-1967 slot; upgrades are performed by the implementation itself
+//! (UUPS), so the proxy has no admin functions of its own.
+//!
+//! Stylus programs have no constructor, so the first call to a fresh proxy must be
+//! `initProxy(address implementation, bytes data)`, which stores the implementation
+//! and delegates `data` (usually the implementation's `init` call) to it. Only the
+//! account baked in at build time through `LANCER_PROXY_INITIALIZER` may send it,
+//! so nobody can front-run the deployer and initialize the proxy with their own admin.
+//!
+//! Calls coming from the implementation's ERC-2771 trusted forwarder carry the
+//! signer's address as a 20 byte calldata suffix. The proxy strips it and exposes
+//! the signer through the forwarded sender slot (see `metatx`).
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{
+    alloy_primitives::Address,
+    call::RawCall,
+    msg,
+    prelude::*,
+    storage::StorageCache,
+    ArbResult,
+};
+use crate::metatx::{forwarded_sender, is_trusted_forwarder, set_forwarded_sender};
+use crate::upgrade::{implementation, set_implementation};
+
+// ====================================
+//             CALLS
+// ====================================
+
+/// Account allowed to send `initProxy`, set when building the proxy
+/// (`LANCER_PROXY_INITIALIZER=0x... cargo build`); without it the proxy can't be initialized
+const INITIALIZER: Option<&str> = option_env!("LANCER_PROXY_INITIALIZER");
+
+sol! {
+    function initProxy(address implementation, bytes data);
+}
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+#[entrypoint]
+fn user_main(input: Vec<u8>) -> ArbResult {
+    let current = implementation();
+
+    // Fresh proxy: only accept the one-time initialization call, from the initializer
+    if current == Address::ZERO {
+        if !is_initializer(msg::sender()) {
+            return Err(Vec::new());
+        }
+        
+        let call = initProxyCall::abi_decode(&input, true).map_err(|_| Vec::new())?;
+        if call.implementation == Address::ZERO {
+            return Err(Vec::new());
+        }
+
+        set_implementation(call.implementation);
+
+        if call.data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Persist the implementation slot before handing over control
+        StorageCache::clear();
+        return RawCall::new_delegate().call(call.implementation, &call.data);
+    }
+
+    // Forwarded meta-transaction: strip the sender suffix before delegating
+    if input.len() >= 20 && is_trusted_forwarder(msg::sender()) {
+        let (data, suffix) = input.split_at(input.len() - 20);
+        let previous = forwarded_sender();
+        set_forwarded_sender(Address::from_slice(suffix));
+
+        StorageCache::clear();
+        let result = RawCall::new_delegate().call(current, data);
+
+        // Restore the previous value, persisted by the entrypoint on exit
+        set_forwarded_sender(previous);
+        return result;
+    }
+
+    StorageCache::clear();
+    RawCall::new_delegate().call(current, &input)
+}
+
+fn is_initializer(account: Address) -> bool {
+    INITIALIZER.and_then(|initializer| initializer.parse::<Address>().ok()) == Some(account)
+}
@@ -0,0 +1,2 @@
+pub mod score;
+pub use score::*;
@@ -0,0 +1,112 @@
+//!
+//! Lancer Reputation - Arbitrum Stylus Implementation
+//!
+//! Decaying reputation scores shared by Marketplace users and Protocol judges.
+//! Scores are fixed-point (`SCALE` per reputation point) and decay toward the
+//! neutral score of zero: they halve every `HALF_LIFE`, linearly in between, so
+//! old outcomes weigh less than recent ones.
+//!
+//! Decay is applied lazily: the stored score is only brought up to date when it
+//! changes, and `current` decays it on read.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use stylus_sdk::{
+    alloy_primitives::{I128, U64},
+    block,
+    prelude::*,
+};
+
+// ====================================
+//          STORAGE STRUCTS
+// ====================================
+
+sol_storage! {
+    pub struct ReputationScore {
+        int128 score;  // Fixed-point, as of updated_at
+        uint64 updated_at;
+    }
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// Fixed-point units per reputation point
+pub const SCALE: i128 = 1_000_000;
+
+/// Time after which a score has decayed to half its value (180 days)
+pub const HALF_LIFE: u64 = 180 * 86400;
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+impl ReputationScore {
+    /// Current score, decayed since its last update
+    pub fn current(&self) -> i128 {
+        let score = i128::from_le_bytes(self.score.get().to_le_bytes());
+        let updated_at = u64::from_le_bytes(self.updated_at.get().to_le_bytes());
+        decay(score, block::timestamp().saturating_sub(updated_at))
+    }
+
+    /// Add `points` whole reputation points to the decayed score and return the new score
+    pub fn add_points(&mut self, points: i64) -> i128 {
+        let score = self.current().saturating_add(points as i128 * SCALE);
+        self.score.set(I128::from_le_bytes(score.to_le_bytes()));
+        self.updated_at.set(U64::from(block::timestamp()));
+        score
+    }
+}
+
+/// Decay `score` toward zero over `elapsed` seconds
+pub fn decay(score: i128, elapsed: u64) -> i128 {
+    let half_lives = elapsed / HALF_LIFE;
+    if half_lives >= 64 {
+        return 0;
+    }
+
+    // Halve once per full half-life, then interpolate linearly toward the next halving
+    let halved = score / (1i128 << half_lives);
+    let into_half_life = (elapsed % HALF_LIFE) as i128;
+    halved - halved * into_half_life / (2 * HALF_LIFE as i128)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decay_halves_every_half_life() {
+        assert_eq!(decay(8 * SCALE, 0), 8 * SCALE);
+        assert_eq!(decay(8 * SCALE, HALF_LIFE), 4 * SCALE);
+        assert_eq!(decay(8 * SCALE, 3 * HALF_LIFE), SCALE);
+    }
+
+    #[test]
+    fn decay_is_linear_within_a_half_life() {
+        // Halfway to the first halving: three quarters of the score remain
+        assert_eq!(decay(8 * SCALE, HALF_LIFE / 2), 6 * SCALE);
+    }
+
+    #[test]
+    fn decay_moves_negative_scores_toward_zero() {
+        assert_eq!(decay(-8 * SCALE, HALF_LIFE), -4 * SCALE);
+        assert_eq!(decay(-8 * SCALE, HALF_LIFE / 2), -6 * SCALE);
+        assert!(decay(-SCALE, 10 * HALF_LIFE) <= 0);
+    }
+
+    #[test]
+    fn decay_reaches_zero_after_64_half_lives() {
+        assert_eq!(decay(i128::MAX, 64 * HALF_LIFE), 0);
+        assert_eq!(decay(i128::MIN, 64 * HALF_LIFE), 0);
+        assert_eq!(decay(SCALE, u64::MAX), 0);
+        assert_eq!(decay(i128::MIN, 63 * HALF_LIFE), -(1i128 << 64));
+    }
+}
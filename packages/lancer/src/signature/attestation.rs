@@ -0,0 +1,215 @@
+//!
+//! Lancer Reputation Attestations - Arbitrum Stylus Implementation
+//!
+//! EIP-712 typed reputation attestations issued by the Marketplace, and the
+//! helpers partner contracts use to verify them.
+//!
+//! The Marketplace cannot hold a private key, so it "signs" the way contract
+//! accounts do: `attestReputation` records the attestation digest and
+//! `isValidSignature` (ERC-1271) confirms it. Verifiers only need the
+//! Marketplace address and the attested values, never its storage layout.
+//!
+//! Users attest their own record, and only their latest attestation verifies.
+//! It stays valid until they attest again, so verifiers must also bound its age.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{fixed_bytes, Address, FixedBytes, U256},
+    call::StaticCallContext,
+    crypto::keccak,
+    prelude::*,
+};
+use super::{domain_separator_for, hash_typed_data};
+
+sol_interface! {
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4);
+    }
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// EIP-712 domain of the Marketplace, its verifying contract is the Marketplace proxy
+pub const ATTESTATION_DOMAIN_NAME: &str = "LancerMarketplace";
+pub const ATTESTATION_DOMAIN_VERSION: &str = "1";
+
+/// keccak256("ReputationAttestation(address user,uint64 completedDeals,uint256 volume,uint64 disputesWon,uint64 disputesLost,uint64 ratingsCount,uint64 ratingAverage,int128 reputation,uint64 issuedAt)")
+pub const REPUTATION_ATTESTATION_TYPEHASH: FixedBytes<32> =
+    fixed_bytes!("df28f489232c9d71c58cf08b468f712fa48bbc6c8e1a0e45f47d351a22972999");
+
+/// bytes4(keccak256("isValidSignature(bytes32,bytes)"))
+pub const ERC1271_MAGIC_VALUE: FixedBytes<4> = fixed_bytes!("1626ba7e");
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+/// A user's Lancer track record at `issued_at`
+/// `volume` is in the Marketplace's default token, `rating_average` in hundredths
+/// and `reputation` is the decayed fixed-point score (see `reputation::SCALE`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReputationAttestation {
+    pub user: Address,
+    pub completed_deals: u64,
+    pub volume: U256,
+    pub disputes_won: u64,
+    pub disputes_lost: u64,
+    pub ratings_count: u64,
+    pub rating_average: u64,
+    pub reputation: i128,
+    pub issued_at: u64,
+}
+
+impl ReputationAttestation {
+    /// EIP-712 struct hash
+    pub fn struct_hash(&self) -> FixedBytes<32> {
+        let mut encoded = Vec::with_capacity(10 * 32);
+        encoded.extend_from_slice(REPUTATION_ATTESTATION_TYPEHASH.as_slice());
+        encoded.extend_from_slice(self.user.into_word().as_slice());
+        encoded.extend_from_slice(&U256::from(self.completed_deals).to_be_bytes::<32>());
+        encoded.extend_from_slice(&self.volume.to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(self.disputes_won).to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(self.disputes_lost).to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(self.ratings_count).to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(self.rating_average).to_be_bytes::<32>());
+        encoded.extend_from_slice(&encode_int128(self.reputation));
+        encoded.extend_from_slice(&U256::from(self.issued_at).to_be_bytes::<32>());
+        keccak(&encoded)
+    }
+
+    /// EIP-712 digest under the domain of the Marketplace at `marketplace` on `chain_id`
+    pub fn digest(&self, chain_id: u64, marketplace: Address) -> FixedBytes<32> {
+        let domain = domain_separator_for(
+            ATTESTATION_DOMAIN_NAME,
+            ATTESTATION_DOMAIN_VERSION,
+            chain_id,
+            marketplace,
+        );
+        hash_typed_data(domain, self.struct_hash())
+    }
+}
+
+/// Check that the Marketplace at `marketplace` issued `attestation` on `chain_id`
+/// and it is the user's latest one. Callers must also enforce a maximum age by
+/// comparing `issued_at` with the current block
+pub fn verify_attestation(
+    context: impl StaticCallContext,
+    chain_id: u64,
+    marketplace: Address,
+    attestation: &ReputationAttestation,
+) -> bool {
+    let digest = attestation.digest(chain_id, marketplace);
+    let verifier = IERC1271::new(marketplace);
+    matches!(
+        verifier.is_valid_signature(context, digest, Vec::new().into()),
+        Ok(magic) if magic == ERC1271_MAGIC_VALUE
+    )
+}
+
+/// ABI encode a signed integer as a sign-extended 32 byte word
+fn encode_int128(value: i128) -> [u8; 32] {
+    let mut word = if value < 0 { [0xff; 32] } else { [0; 32] };
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_primitives::address;
+
+    mod reference {
+        alloy_sol_types::sol! {
+            struct ReputationAttestation {
+                address user;
+                uint64 completedDeals;
+                uint256 volume;
+                uint64 disputesWon;
+                uint64 disputesLost;
+                uint64 ratingsCount;
+                uint64 ratingAverage;
+                int128 reputation;
+                uint64 issuedAt;
+            }
+        }
+    }
+
+    fn attestation(reputation: i128) -> ReputationAttestation {
+        ReputationAttestation {
+            user: address!("70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+            completed_deals: 12,
+            volume: U256::from(25_000_000_000u64),
+            disputes_won: 2,
+            disputes_lost: 1,
+            ratings_count: 9,
+            rating_average: 467,
+            reputation,
+            issued_at: 1_700_000_000,
+        }
+    }
+
+    fn reference_hash(attestation: &ReputationAttestation) -> FixedBytes<32> {
+        use alloy_sol_types::SolStruct;
+        reference::ReputationAttestation {
+            user: attestation.user,
+            completedDeals: attestation.completed_deals,
+            volume: attestation.volume,
+            disputesWon: attestation.disputes_won,
+            disputesLost: attestation.disputes_lost,
+            ratingsCount: attestation.ratings_count,
+            ratingAverage: attestation.rating_average,
+            reputation: attestation.reputation,
+            issuedAt: attestation.issued_at,
+        }
+        .eip712_hash_struct()
+    }
+
+    #[test]
+    fn encode_int128_sign_extends() {
+        assert_eq!(encode_int128(0), [0; 32]);
+        assert_eq!(encode_int128(-1), [0xff; 32]);
+
+        let mut one = [0; 32];
+        one[31] = 1;
+        assert_eq!(encode_int128(1), one);
+
+        let mut min = [0xff; 32];
+        min[16] = 0x80;
+        min[17..].fill(0);
+        assert_eq!(encode_int128(i128::MIN), min);
+    }
+
+    #[test]
+    fn typehash_matches_the_type_string() {
+        use alloy_sol_types::SolStruct;
+        let attestation = reference::ReputationAttestation {
+            user: Address::ZERO,
+            completedDeals: 0,
+            volume: U256::ZERO,
+            disputesWon: 0,
+            disputesLost: 0,
+            ratingsCount: 0,
+            ratingAverage: 0,
+            reputation: 0,
+            issuedAt: 0,
+        };
+        assert_eq!(attestation.eip712_type_hash(), REPUTATION_ATTESTATION_TYPEHASH);
+    }
+
+    #[test]
+    fn struct_hash_matches_eip712_encoding() {
+        for reputation in [0, 3_500_000, -3_500_000, i128::MAX, i128::MIN] {
+            let attestation = attestation(reputation);
+            assert_eq!(attestation.struct_hash(), reference_hash(&attestation));
+        }
+    }
+}
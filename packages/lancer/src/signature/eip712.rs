@@ -0,0 +1,91 @@
+//!
+//! Lancer Signatures - Arbitrum Stylus Implementation
+//!
+//! EIP-712 typed data hashing and ECDSA recovery shared by the contracts that
+//! accept off-chain signatures (Mock USDC permits, the meta-transaction forwarder)
+//! and by reputation attestations. Recovery goes through the ecrecover precompile.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{address, fixed_bytes, Address, FixedBytes, U256},
+    block,
+    call::RawCall,
+    contract,
+    crypto::keccak,
+};
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)")
+pub const DOMAIN_TYPEHASH: FixedBytes<32> =
+    fixed_bytes!("8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f");
+
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+/// EIP-712 domain separator of the current contract on the current chain
+pub fn domain_separator(name: &str, version: &str) -> FixedBytes<32> {
+    domain_separator_for(name, version, block::chainid(), contract::address())
+}
+
+/// EIP-712 domain separator of any contract, to check typed data another contract issued
+pub fn domain_separator_for(
+    name: &str,
+    version: &str,
+    chain_id: u64,
+    verifying_contract: Address,
+) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(5 * 32);
+    encoded.extend_from_slice(DOMAIN_TYPEHASH.as_slice());
+    encoded.extend_from_slice(keccak(name.as_bytes()).as_slice());
+    encoded.extend_from_slice(keccak(version.as_bytes()).as_slice());
+    encoded.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+    encoded.extend_from_slice(verifying_contract.into_word().as_slice());
+    keccak(&encoded)
+}
+
+/// Digest to sign for a typed struct: keccak256("\x19\x01" ‖ domainSeparator ‖ structHash)
+pub fn hash_typed_data(domain_separator: FixedBytes<32>, struct_hash: FixedBytes<32>) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(2 + 2 * 32);
+    encoded.extend_from_slice(&[0x19, 0x01]);
+    encoded.extend_from_slice(domain_separator.as_slice());
+    encoded.extend_from_slice(struct_hash.as_slice());
+    keccak(&encoded)
+}
+
+/// Recover the signer of `digest` (zero address on failure)
+pub fn recover(digest: FixedBytes<32>, v: u8, r: FixedBytes<32>, s: FixedBytes<32>) -> Address {
+    let mut input = Vec::with_capacity(4 * 32);
+    input.extend_from_slice(digest.as_slice());
+    input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    input.extend_from_slice(r.as_slice());
+    input.extend_from_slice(s.as_slice());
+
+    match RawCall::new_static().call(ECRECOVER, &input) {
+        Ok(output) if output.len() == 32 => Address::from_slice(&output[12..]),
+        _ => Address::ZERO,
+    }
+}
+
+/// Recover the signer of `digest` from a packed 65 byte `r ‖ s ‖ v` signature
+pub fn recover_packed(digest: FixedBytes<32>, signature: &[u8]) -> Address {
+    if signature.len() != 65 {
+        return Address::ZERO;
+    }
+
+    let r = FixedBytes::<32>::from_slice(&signature[..32]);
+    let s = FixedBytes::<32>::from_slice(&signature[32..64]);
+    recover(digest, signature[64], r, s)
+}
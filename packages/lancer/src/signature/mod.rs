@@ -0,0 +1,4 @@
+pub mod eip712;
+pub mod attestation;
+pub use eip712::*;
+pub use attestation::*;
@@ -0,0 +1,2 @@
+pub mod uups;
+pub use uups::*;
@@ -0,0 +1,140 @@
+//!
+//! Lancer UUPS Upgrades - Arbitrum Stylus Implementation
+//!
+//! Helpers used by the Marketplace and Protocol contracts to upgrade themselves
+//! when deployed behind the Lancer proxy (see `proxy`). The implementation
+//! address lives in the EIP-1967 slot so it can never collide with the
+//! `sol_storage!` layout of the contracts, which starts at slot 0.
+//!
+//! Each upgradeable contract declares a `STORAGE_VERSION` constant, records the
+//! version it was initialized or migrated to, and gates upgrades behind its own
+//! authorization hook. Upgrading is a two step process: `upgradeTo` switches the
+//! implementation, then `migrate` brings the storage up to the new version.
+//!
+//! @author 0xDarioSanchez
+//!
+//! Note: this code has not been audited.
+//!
+
+extern crate alloc;
+
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    alloy_primitives::{uint, Address, FixedBytes, U256},
+    call::Call,
+    evm,
+    prelude::*,
+    storage::{GlobalStorage, StorageCache},
+};
+
+// ====================================
+//             EVENTS
+// ====================================
+
+sol! {
+    event Upgraded(address indexed implementation);
+    event Migrated(uint64 from_version, uint64 to_version);
+
+    error NotDelegated();
+    error UnsupportedImplementation(address implementation);
+    error LayoutDowngrade(uint64 current_version, uint64 new_version);
+}
+
+// ====================================
+//          ERROR TYPES
+// ====================================
+
+#[derive(SolidityError)]
+pub enum UpgradeError {
+    NotDelegated(NotDelegated),
+    UnsupportedImplementation(UnsupportedImplementation),
+    LayoutDowngrade(LayoutDowngrade),
+}
+
+// ====================================
+//        CONSTANTS
+// ====================================
+
+/// keccak256("eip1967.proxy.implementation") - 1
+pub const IMPLEMENTATION_SLOT: U256 =
+    uint!(0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc_U256);
+
+// ====================================
+//      EXTERNAL INTERFACE CALLS
+// ====================================
+
+sol_interface! {
+    interface IProxiable {
+        function proxiableUUID() external view returns (bytes32);
+        function layoutVersion() external pure returns (uint64);
+    }
+}
+
+// ====================================
+//        IMPLEMENTATION
+// ====================================
+
+/// Read the implementation address stored in the EIP-1967 slot
+pub fn implementation() -> Address {
+    Address::from_word(StorageCache::get_word(IMPLEMENTATION_SLOT))
+}
+
+/// Write the implementation address to the EIP-1967 slot
+pub fn set_implementation(new_implementation: Address) {
+    unsafe { StorageCache::set_word(IMPLEMENTATION_SLOT, new_implementation.into_word()) };
+    evm::log(Upgraded { implementation: new_implementation });
+}
+
+/// The slot returned by `proxiableUUID` (ERC-1822)
+pub fn proxiable_uuid() -> FixedBytes<32> {
+    FixedBytes::from(IMPLEMENTATION_SLOT.to_be_bytes::<32>())
+}
+
+/// Revert unless the call is running in the proxy's storage context
+pub fn only_proxy() -> Result<(), UpgradeError> {
+    if implementation() == Address::ZERO {
+        return Err(UpgradeError::NotDelegated(NotDelegated {}));
+    }
+    Ok(())
+}
+
+/// Point the proxy at `new_implementation`
+///
+/// The caller is responsible for authorizing the upgrade. The new implementation
+/// must be proxiable and must not declare an older storage layout than the one
+/// currently recorded in storage.
+pub fn upgrade_to<S: TopLevelStorage>(
+    storage: &mut S,
+    current_version: u64,
+    new_implementation: Address,
+) -> Result<(), UpgradeError> {
+    only_proxy()?;
+
+    let target = IProxiable::new(new_implementation);
+    let uuid = target
+        .proxiable_uuid(Call::new_in(storage))
+        .map_err(|_| UpgradeError::UnsupportedImplementation(UnsupportedImplementation {
+            implementation: new_implementation,
+        }))?;
+    if uuid != proxiable_uuid() {
+        return Err(UpgradeError::UnsupportedImplementation(UnsupportedImplementation {
+            implementation: new_implementation,
+        }));
+    }
+
+    let new_version = target
+        .layout_version(Call::new_in(storage))
+        .map_err(|_| UpgradeError::UnsupportedImplementation(UnsupportedImplementation {
+            implementation: new_implementation,
+        }))?;
+    if new_version < current_version {
+        return Err(UpgradeError::LayoutDowngrade(LayoutDowngrade {
+            current_version,
+            new_version,
+        }));
+    }
+
+    set_implementation(new_implementation);
+
+    Ok(())
+}
@@ -278,7 +278,8 @@ echo "=========================================="
 echo "📋 STEP 11: Approve Dispute Fee"
 echo "=========================================="
 
-DISPUTE_FEE="50000000"  # 50 USDC (6 decimals)
+# Quoted by the protocol from the deal value (6 decimals)
+DISPUTE_FEE=$(cast call $MARKETPLACE_ADDRESS "getDisputeFeeQuote(uint64)(uint256)" $DEAL_ID --rpc-url $RPC_URL | awk '{print $1}')
 
 echo "Buyer approving marketplace to spend $DISPUTE_FEE USDC for dispute fee..."
 cast send $USDC_ADDRESS \
@@ -307,11 +308,24 @@ cast send $MARKETPLACE_ADDRESS \
 
 echo "✅ Dispute created"
 
-# Get dispute ID from protocol counter (counter points to next id)
-NEXT_DISPUTE_ID_HEX=$(cast call $PROTOCOL_ADDRESS "disputeCount()" --rpc-url $RPC_URL | tr -d '\r\n')
-NEXT_DISPUTE_ID_DEC=$(cast --to-dec "$NEXT_DISPUTE_ID_HEX" | tr -d '\r\n')
-DISPUTE_ID=$((NEXT_DISPUTE_ID_DEC - 1))
-if [ "$DISPUTE_ID" -lt 0 ]; then DISPUTE_ID=0; fi
+echo "Seller matching the dispute fee..."
+cast send $USDC_ADDRESS \
+    "approve(address,uint256)" \
+    $MARKETPLACE_ADDRESS $DISPUTE_FEE \
+    --private-key $SELLER_KEY \
+    --rpc-url $RPC_URL \
+    --gas-limit 2000000
+
+cast send $MARKETPLACE_ADDRESS \
+    "matchDisputeFee(uint64)" \
+    $DEAL_ID \
+    --private-key $SELLER_KEY \
+    --rpc-url $RPC_URL \
+    --gas-limit 5000000
+
+echo "✅ Both dispute fees deposited, case forwarded to the protocol"
+
+DISPUTE_ID=$(cast call $MARKETPLACE_ADDRESS "getDealDispute(uint64)(uint64)" $DEAL_ID --rpc-url $RPC_URL | tr -d '\r\n')
 echo "Dispute ID: $DISPUTE_ID"
 echo ""
 
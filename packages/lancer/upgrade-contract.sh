@@ -0,0 +1,91 @@
+#!/bin/bash
+
+# Upgrade the Protocol or Marketplace implementation behind its proxy
+# Usage: ./upgrade-contract.sh <protocol|marketplace>
+
+set -e  # Exit on error
+
+cd "$(dirname "$0")"
+
+CONTRACT=$1
+if [ "$CONTRACT" != "protocol" ] && [ "$CONTRACT" != "marketplace" ]; then
+    echo "Usage: $0 <protocol|marketplace>"
+    exit 1
+fi
+
+# Deployer key (must hold ADMIN_ROLE on the proxy)
+DEPLOYER_KEY="0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+RPC_URL="http://127.0.0.1:8547"
+
+PROXY_ADDR=$(grep -A1 "\"${CONTRACT}_contract\"" build/stylus-deployment-info.json | grep "address" | grep -oE '0x[a-fA-F0-9]{40}')
+if [ -z "$PROXY_ADDR" ]; then
+    echo "No $CONTRACT proxy found in build/stylus-deployment-info.json"
+    exit 1
+fi
+
+echo "=========================================="
+echo "⬆️  Upgrading $CONTRACT at $PROXY_ADDR"
+echo "=========================================="
+
+OLD_VERSION=$(cast call $PROXY_ADDR "storageVersion()(uint64)" --rpc-url $RPC_URL)
+echo "Current storage version: $OLD_VERSION"
+
+cat > src/lib.rs << EOF
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+pub mod $CONTRACT;
+EOF
+
+cargo build --release --target wasm32-unknown-unknown 2>&1 | grep -E "(Compiling|Finished)"
+NEW_IMPL=$(cargo stylus deploy --no-verify --private-key $DEPLOYER_KEY --endpoint $RPC_URL 2>&1 | grep "deployed code at address" | awk '{print $NF}')
+echo "New implementation deployed at: $NEW_IMPL"
+
+# Restore the default module selection
+cat > src/lib.rs << 'EOF'
+#![cfg_attr(not(feature = "export-abi"), no_main)]
+pub mod access;
+pub mod upgrade;
+pub mod signature;
+pub mod metatx;
+pub mod reputation;
+pub mod protocol;
+// pub mod marketplace;
+// pub mod mocks;
+// pub mod proxy;
+// pub mod forwarder;
+// pub mod credentials;
+// pub mod positions;
+EOF
+
+cast send $PROXY_ADDR "upgradeTo(address)" $NEW_IMPL --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null
+echo "✅ Proxy now points to $(cast call $PROXY_ADDR "implementation()(address)" --rpc-url $RPC_URL)"
+
+NEW_VERSION=$(cast call $NEW_IMPL "layoutVersion()(uint64)" --rpc-url $RPC_URL)
+if [ "$NEW_VERSION" != "$OLD_VERSION" ]; then
+    echo "Migrating storage from version $OLD_VERSION to $NEW_VERSION..."
+    if [ "$CONTRACT" = "marketplace" ]; then
+        # Deal records are rewritten in bounded batches before the version bump
+        DEPLOYER_ADDR=$(cast wallet address --private-key $DEPLOYER_KEY)
+        REMAINING=1
+        while [ "$REMAINING" != "0" ]; do
+            REMAINING=$(cast call $PROXY_ADDR "migrateDeals(uint64)(uint64)" 500 --from $DEPLOYER_ADDR --rpc-url $RPC_URL)
+            cast send $PROXY_ADDR "migrateDeals(uint64)" 500 --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 30000000 > /dev/null
+            echo "  $REMAINING deals left to migrate"
+        done
+    fi
+    cast send $PROXY_ADDR "migrate()" --private-key $DEPLOYER_KEY --rpc-url $RPC_URL --gas-limit 5000000 > /dev/null
+    echo "✅ Storage migrated"
+else
+    echo "Storage layout unchanged, no migration needed"
+fi
+
+# Keep the implementation address in the deployment info up to date
+sed -i "/\"${CONTRACT}_contract\"/,/}/ s/\"implementation\": \"0x[a-fA-F0-9]*\"/\"implementation\": \"$NEW_IMPL\"/" build/stylus-deployment-info.json
+
+echo "=========================================="
+echo "✅ Upgrade Complete!"
+echo "=========================================="